@@ -0,0 +1,260 @@
+use std::fmt;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, MessageInfo, StdResult, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+
+use crate::error::ContractError;
+use crate::msg::FundsMode;
+
+#[cw_serde]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+    /// A single NFT, always used with an amount of 1
+    Cw721 {
+        contract: Addr,
+        token_id: String,
+    },
+}
+
+impl AssetInfo {
+    /// Native denom or token contract address, NFTs are grouped by collection
+    pub fn denom(&self) -> String {
+        match self {
+            AssetInfo::Native(denom) => denom.clone(),
+            AssetInfo::Cw20(contract) | AssetInfo::Cw721 { contract, .. } => contract.to_string(),
+        }
+    }
+
+    pub fn is_nft(&self) -> bool {
+        matches!(self, AssetInfo::Cw721 { .. })
+    }
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetInfo::Native(denom) => write!(f, "{}", denom),
+            AssetInfo::Cw20(contract) => write!(f, "{}", contract),
+            AssetInfo::Cw721 { contract, token_id } => write!(f, "{}/{}", contract, token_id),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+impl Asset {
+    pub fn native(amount: impl Into<Uint128>, denom: impl Into<String>) -> Self {
+        Asset {
+            info: AssetInfo::Native(denom.into()),
+            amount: amount.into(),
+        }
+    }
+
+    pub fn cw20(amount: impl Into<Uint128>, contract: Addr) -> Self {
+        Asset {
+            info: AssetInfo::Cw20(contract),
+            amount: amount.into(),
+        }
+    }
+
+    pub fn cw721(contract: Addr, token_id: impl Into<String>) -> Self {
+        Asset {
+            info: AssetInfo::Cw721 {
+                contract,
+                token_id: token_id.into(),
+            },
+            amount: Uint128::one(),
+        }
+    }
+
+    /// Same asset with another amount
+    pub fn with_amount(&self, amount: Uint128) -> Self {
+        Asset {
+            info: self.info.clone(),
+            amount,
+        }
+    }
+
+    pub fn transfer_msg(&self, recipient: &Addr) -> StdResult<CosmosMsg> {
+        match &self.info {
+            AssetInfo::Native(denom) => Ok(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount: self.amount,
+                }],
+            }
+            .into()),
+            AssetInfo::Cw20(contract) => Ok(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: self.amount,
+                })?,
+                funds: vec![],
+            }
+            .into()),
+            AssetInfo::Cw721 { contract, token_id } => Ok(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.clone(),
+                })?,
+                funds: vec![],
+            }
+            .into()),
+        }
+    }
+}
+
+impl From<Coin> for Asset {
+    fn from(coin: Coin) -> Self {
+        Asset::native(coin.amount, coin.denom)
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.info)
+    }
+}
+
+/// Sums up entries of the same asset and drops empty ones
+pub fn merge_assets(assets: Vec<Asset>) -> Vec<Asset> {
+    let mut merged: Vec<Asset> = Vec::new();
+    for asset in assets.into_iter().filter(|asset| !asset.amount.is_zero()) {
+        match merged.iter_mut().find(|m| m.info == asset.info) {
+            Some(existing) => existing.amount += asset.amount,
+            None => merged.push(asset),
+        }
+    }
+    merged
+}
+
+/// What `held` lacks to cover `required`, and what it holds in excess of it
+pub fn diff_assets(required: Vec<Asset>, held: Vec<Asset>) -> (Vec<Asset>, Vec<Asset>) {
+    let mut held = merge_assets(held);
+    let mut missing: Vec<Asset> = Vec::new();
+
+    for asset in merge_assets(required) {
+        match held.iter_mut().find(|h| h.info == asset.info) {
+            Some(found) if found.amount >= asset.amount => found.amount -= asset.amount,
+            Some(found) => {
+                missing.push(asset.with_amount(asset.amount - found.amount));
+                found.amount = Uint128::zero();
+            }
+            None => missing.push(asset),
+        }
+    }
+
+    (missing, merge_assets(held))
+}
+
+/// Sender of an execute call and the assets attached to it, either the
+/// native funds, the tokens of a CW20 `Send` or the NFT of a CW721 `SendNft`
+pub struct Payment {
+    pub sender: Addr,
+    pub assets: Vec<Asset>,
+    pub funds_mode: FundsMode,
+}
+
+impl Payment {
+    pub fn from_info(info: &MessageInfo) -> Self {
+        Payment {
+            sender: info.sender.clone(),
+            assets: info.funds.iter().cloned().map(Asset::from).collect(),
+            funds_mode: FundsMode::default(),
+        }
+    }
+
+    /// `info.sender` is the token contract, the actual sender comes with the hook
+    pub fn from_cw20(api: &dyn Api, info: &MessageInfo, msg: &Cw20ReceiveMsg) -> StdResult<Self> {
+        Ok(Payment {
+            sender: api.addr_validate(&msg.sender)?,
+            assets: vec![Asset::cw20(msg.amount, info.sender.clone())],
+            funds_mode: FundsMode::default(),
+        })
+    }
+
+    /// `info.sender` is the NFT contract, the actual sender comes with the hook
+    pub fn from_cw721(api: &dyn Api, info: &MessageInfo, msg: &Cw721ReceiveMsg) -> StdResult<Self> {
+        Ok(Payment {
+            sender: api.addr_validate(&msg.sender)?,
+            assets: vec![Asset::cw721(info.sender.clone(), msg.token_id.clone())],
+            funds_mode: FundsMode::default(),
+        })
+    }
+
+    pub fn with_funds_mode(self, funds_mode: FundsMode) -> Self {
+        Payment { funds_mode, ..self }
+    }
+
+    /// Checks the attached assets cover `required`. CW20 tokens that were not
+    /// sent along are pulled from the sender's allowance instead, the returned
+    /// messages have to run before anything is paid out. NFTs have to be sent.
+    /// Attached assets beyond `required` are rejected or refunded as per `funds_mode`.
+    pub fn collect(
+        &self,
+        required: Vec<Asset>,
+        contract: &Addr,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        let required = merge_assets(required);
+        let mut available = self.assets.clone();
+        let mut msgs: Vec<CosmosMsg> = Vec::new();
+
+        for asset in required.iter().cloned() {
+            match available.iter_mut().find(|a| a.info == asset.info) {
+                Some(found) if found.amount >= asset.amount => found.amount -= asset.amount,
+                Some(_) => return Err(self.payment_error(required)),
+                None => match &asset.info {
+                    AssetInfo::Cw20(token) => msgs.push(
+                        WasmMsg::Execute {
+                            contract_addr: token.to_string(),
+                            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                                owner: self.sender.to_string(),
+                                recipient: contract.to_string(),
+                                amount: asset.amount,
+                            })?,
+                            funds: vec![],
+                        }
+                        .into(),
+                    ),
+                    // The contract could move NFTs it holds in escrow, so they are never pulled
+                    AssetInfo::Cw721 { .. } | AssetInfo::Native(_) => {
+                        return Err(self.payment_error(required))
+                    }
+                },
+            }
+        }
+
+        let excess = merge_assets(available);
+        if !excess.is_empty() {
+            match self.funds_mode {
+                FundsMode::Exact => return Err(self.payment_error(required)),
+                FundsMode::Refund => {
+                    for asset in excess {
+                        msgs.push(asset.transfer_msg(&self.sender)?);
+                    }
+                }
+            }
+        }
+
+        Ok(msgs)
+    }
+
+    fn payment_error(&self, expected: Vec<Asset>) -> ContractError {
+        ContractError::PaymentError {
+            expected,
+            actual: merge_assets(self.assets.clone()),
+        }
+    }
+}
@@ -1,23 +1,23 @@
 use std::env::current_dir;
 
 use cosmwasm_schema::{export_schema, schema_for, write_api};
-use cosmwasm_std::Empty;
-use crypto_pawn::msg::{ExecuteMsg, InstantiateMsg, SudoMsg};
+use crypto_pawn::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
 
 fn main() {
     // Clear & write standard API
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
+        query: QueryMsg,
         sudo: SudoMsg,
-        migrate: Empty,
+        migrate: MigrateMsg,
     }
 
     // Schemas for inter-contract communication
     let mut out_dir = current_dir().unwrap();
     out_dir.push("schema");
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
-    // export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(SudoMsg), &out_dir);
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
 }
@@ -1,18 +1,33 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure, ensure_ne, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo,
-    Order, Response, StdResult, Storage,
+    ensure, ensure_ne, from_binary, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal,
+    Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, QuerierWrapper, Response, StdError,
+    StdResult, Storage, Uint128, Uint64,
 };
-use cw2::set_contract_version;
-use cw_storage_plus::Bound;
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::{Bound, Prefix};
 use cw_utils::NativeBalance;
+use semver::Version;
+use serde::{de::DeserializeOwned, Serialize};
+use crate::asset::{diff_assets, merge_assets, Asset, AssetInfo, Payment};
 use crate::ensure_state;
 use crate::error::ContractError;
+use crate::events::{proposition_event, Transition};
+use crate::migrations::{migrate_propositions_v1, migration_in_progress, DEFAULT_MIGRATION_LIMIT};
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, PropositionState, PropositionType, QueryMsg, SudoMsg, MigrateMsg,
+    CountResponse, ExecuteMsg, FundsMode, InstantiateMsg, Operation, PositionSide,
+    PropositionFilter, PropositionResponse, PropositionState, PropositionType, Payoff,
+    PropositionsPage, PropositionsResponse, QueryMsg, SortOrder, SudoMsg, MigrateMsg,
+};
+use crate::note::{mint_note_msg, note_token_id, resolve_note_holders};
+use crate::oracle::InjectiveQueryWrapper;
+use crate::state::{
+    counter_offers, propositions, Config, CounterOffer, Extension, Fill, LoanToValue, Notes,
+    Proposition, Substitution, SubstitutionRecord, TermLimits, Vault, VaultTerms, CONFIG,
+    COUNTER_OFFER_COUNT, FILLS, MAX_FEE_BPS, MAX_GRACE_PERIOD, PAUSE_FLAGS, PRIVATE_OFFERS, PROPOSITION_COUNT, VAULTS,
+    VAULT_CHECKPOINTS, VAULT_COUNT, VAULT_SHARES,
 };
-use crate::state::{Proposition, PROPOSITIONS, PROPOSITION_COUNT};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:qwerty:crypto-pawn";
@@ -20,31 +35,182 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    deps: DepsMut<InjectiveQueryWrapper>,
     _env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    ensure!(
+        u128::from(msg.fee_bps) <= MAX_FEE_BPS,
+        ContractError::InvalidFee {}
+    );
+
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    let config = Config {
+        owner,
+        treasury: deps.api.addr_validate(&msg.treasury)?,
+        fee_bps: msg.fee_bps,
+        fee_on_default: msg.fee_on_default,
+        note_contract: None,
+        grace_period: 0,
+        late_fee_bps: 0,
+        funds_mode: FundsMode::default(),
+        term_limits: TermLimits::default(),
+        collateral_denoms: vec![],
+        min_collateral_ratio: Decimal::zero(),
+        token_contracts: vec![],
+        oracle_symbols: vec![],
+    };
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", config.owner)
+        .add_attribute("treasury", config.treasury)
+        .add_attribute("fee_bps", config.fee_bps.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    let previous: Version = stored.version.parse()?;
+    let current: Version = CONTRACT_VERSION.parse()?;
+
+    // Only upgrades of this contract, re-running the current version is a no-op
+    ensure!(
+        stored.contract == CONTRACT_NAME && previous <= current,
+        ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+            previous_version: stored.version,
+        }
+    );
+
+    let mut response = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", previous.to_string());
+
+    if previous < Version::new(2, 0, 0) {
+        let limit = msg.limit.unwrap_or(DEFAULT_MIGRATION_LIMIT);
+        let migrated = migrate_propositions_v1(deps.storage, limit)?;
+
+        let (owner, treasury) = match (msg.owner, msg.treasury) {
+            (Some(owner), Some(treasury)) => (owner, treasury),
+            _ => {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "owner and treasury are required to migrate from 1.x",
+                )))
+            }
+        };
+        CONFIG.save(
+            deps.storage,
+            &Config {
+                owner: deps.api.addr_validate(&owner)?,
+                treasury: deps.api.addr_validate(&treasury)?,
+                fee_bps: 0,
+                fee_on_default: false,
+                note_contract: None,
+                grace_period: 0,
+                late_fee_bps: 0,
+                funds_mode: FundsMode::default(),
+                term_limits: TermLimits::default(),
+                collateral_denoms: vec![],
+                min_collateral_ratio: Decimal::zero(),
+                token_contracts: vec![],
+                oracle_symbols: vec![],
+            },
+        )?;
+
+        response = response
+            .add_attribute("migrated_propositions", migrated.to_string())
+            .add_attribute(
+                "migration_done",
+                (!migration_in_progress(deps.storage)?).to_string(),
+            );
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(response.add_attribute("version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Nothing but the migration itself runs on half migrated 1.x storage
+    ensure!(
+        !migration_in_progress(deps.storage)?
+            || matches!(msg, ExecuteMsg::MigratePropositions { .. }),
+        ContractError::MigrationInProgress {}
+    );
+
     match msg {
+        ExecuteMsg::Receive(receive_msg) => {
+            let msg = hooked_msg(deps.storage, &info, &receive_msg.msg)?;
+            let payment = Payment::from_cw20(deps.api, &info, &receive_msg)?;
+            execute_with_payment(deps, env, payment, msg)
+        }
+        ExecuteMsg::ReceiveNft(receive_msg) => {
+            let msg = hooked_msg(deps.storage, &info, &receive_msg.msg)?;
+            let payment = Payment::from_cw721(deps.api, &info, &receive_msg)?;
+            execute_with_payment(deps, env, payment, msg)
+        }
+        msg => execute_with_payment(deps, env, Payment::from_info(&info), msg),
+    }
+}
+
+/// Message carried by a token hook. Only a trusted token vouches for the
+/// sender it reports, and only for paying messages.
+fn hooked_msg(
+    storage: &dyn Storage,
+    info: &MessageInfo,
+    msg: &Binary,
+) -> Result<ExecuteMsg, ContractError> {
+    ensure!(
+        CONFIG.load(storage)?.token_contracts.contains(&info.sender),
+        ContractError::UntrustedToken {}
+    );
+    let msg: ExecuteMsg = from_binary(msg)?;
+    ensure!(msg.is_payable(), ContractError::InvalidReceiveMsg {});
+    Ok(msg)
+}
+
+fn execute_with_payment(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let payment = payment.with_funds_mode(CONFIG.load(deps.storage)?.funds_mode);
+
+    if let Some(operation) = Operation::of(&msg) {
+        let flags = PAUSE_FLAGS.may_load(deps.storage)?.unwrap_or_default();
+        ensure!(
+            !flags.is_paused(operation),
+            ContractError::Paused { operation }
+        );
+    }
+
+    // Messages that take no payment keep nothing attached to them
+    let refunds = if msg.is_payable() {
+        vec![]
+    } else {
+        payment.collect(vec![], &env.contract.address)?
+    };
+
+    let response = match msg {
         ExecuteMsg::CreateProposition {
             proposition_type,
             deposit,
@@ -53,10 +219,15 @@ pub fn execute(
             period,
             expiry,
             contractor,
+            designated_contractors,
+            installments,
+            ltv,
+            min_fill,
+            interest_rate,
         } => create_proposition(
             deps,
             env,
-            _info,
+            payment,
             proposition_type,
             deposit,
             assets,
@@ -64,34 +235,331 @@ pub fn execute(
             period,
             expiry,
             contractor,
+            designated_contractors,
+            installments,
+            ltv,
+            min_fill,
+            interest_rate,
         ),
 
         ExecuteMsg::AcceptProposition { proposition_id } => {
-            accept_proposition(deps, env, _info, proposition_id)
+            accept_proposition(deps, env, payment, proposition_id)
+        }
+
+        ExecuteMsg::FillProposition {
+            proposition_id,
+            amount,
+        } => fill_proposition(deps, env, payment, proposition_id, amount),
+
+        ExecuteMsg::ActivateProposition { proposition_id } => {
+            activate_proposition(deps, env, payment, proposition_id)
         }
 
         ExecuteMsg::RejectProposition { proposition_id } => {
-            reject_proposition(deps, env, _info, proposition_id)
+            reject_proposition(deps, env, payment, proposition_id)
         }
 
         ExecuteMsg::CloseProposition { proposition_id } => {
-            close_proposition(deps, env, _info, proposition_id)
+            close_proposition(deps, env, payment, proposition_id)
         }
-    }
+
+        ExecuteMsg::RepayLoan {
+            proposition_id,
+            amount,
+        } => repay_loan(deps, env, payment, proposition_id, amount),
+
+        ExecuteMsg::RequestExtension {
+            proposition_id,
+            extra_period,
+            extra_premium,
+        } => request_extension(
+            deps,
+            env,
+            payment,
+            proposition_id,
+            extra_period,
+            extra_premium,
+        ),
+
+        ExecuteMsg::ApproveExtension { proposition_id } => {
+            approve_extension(deps, env, payment, proposition_id)
+        }
+
+        ExecuteMsg::Liquidate { proposition_id } => liquidate(deps, env, payment, proposition_id),
+
+        ExecuteMsg::AddCollateral { proposition_id } => {
+            add_collateral(deps, env, payment, proposition_id)
+        }
+
+        ExecuteMsg::WithdrawExcessCollateral {
+            proposition_id,
+            amount,
+            collateral,
+        } => withdraw_excess_collateral(deps, env, payment, proposition_id, amount, collateral),
+
+        ExecuteMsg::SubstituteCollateral {
+            proposition_id,
+            new_deposit,
+        } => substitute_collateral(deps, env, payment, proposition_id, new_deposit),
+
+        ExecuteMsg::ApproveSubstitution { proposition_id } => {
+            approve_substitution(deps, env, payment, proposition_id)
+        }
+
+        ExecuteMsg::UpdateConfig {
+            owner,
+            treasury,
+            fee_bps,
+            fee_on_default,
+            note_contract,
+            grace_period,
+            late_fee_bps,
+            funds_mode,
+            term_limits,
+            collateral_denoms,
+            min_collateral_ratio,
+            token_contracts,
+            oracle_symbols,
+        } => update_config(
+            deps,
+            payment,
+            owner,
+            treasury,
+            fee_bps,
+            fee_on_default,
+            note_contract,
+            grace_period,
+            late_fee_bps,
+            funds_mode,
+            term_limits,
+            collateral_denoms,
+            min_collateral_ratio,
+            token_contracts,
+            oracle_symbols,
+        ),
+
+        ExecuteMsg::MintNote {
+            proposition_id,
+            side,
+        } => mint_note(deps, payment, proposition_id, side),
+
+        ExecuteMsg::MakeCounterOffer {
+            proposition_id,
+            deposit,
+            assets,
+            premium,
+            period,
+        } => make_counter_offer(
+            deps,
+            env,
+            payment,
+            proposition_id,
+            deposit,
+            assets,
+            premium,
+            period,
+        ),
+
+        ExecuteMsg::AcceptCounterOffer { counter_offer_id } => {
+            accept_counter_offer(deps, env, payment, counter_offer_id)
+        }
+
+        ExecuteMsg::WithdrawCounterOffer { counter_offer_id } => {
+            withdraw_counter_offer(deps, payment, counter_offer_id)
+        }
+
+        ExecuteMsg::TransferPosition {
+            proposition_id,
+            side,
+            recipient,
+        } => transfer_position(deps, payment, proposition_id, side, recipient),
+
+        ExecuteMsg::CreateVault { assets, terms } => create_vault(deps, payment, assets, terms),
+
+        ExecuteMsg::DepositToVault { vault_id, amount } => {
+            deposit_to_vault(deps, env, payment, vault_id, amount)
+        }
+
+        ExecuteMsg::WithdrawFromVault { vault_id, shares } => {
+            withdraw_from_vault(deps, payment, vault_id, shares)
+        }
+
+        ExecuteMsg::BorrowFromVault { vault_id, amount } => {
+            borrow_from_vault(deps, env, payment, vault_id, amount)
+        }
+
+        ExecuteMsg::SetPaused { operation, paused } => {
+            ensure!(
+                CONFIG.load(deps.storage)?.owner == payment.sender,
+                ContractError::Unauthorized {}
+            );
+            set_paused(deps, operation, paused)
+        }
+
+        ExecuteMsg::MigratePropositions { limit } => migrate_propositions(deps, limit),
+
+        ExecuteMsg::Receive(_) | ExecuteMsg::ReceiveNft(_) => {
+            Err(ContractError::InvalidReceiveMsg {})
+        }
+    }?;
+
+    Ok(response.add_messages(refunds))
 }
 
 #[entry_point]
-pub fn sudo(_deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+pub fn sudo(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response, ContractError> {
     match msg {
-        SudoMsg::BeginBlocker {} => Ok(Response::new()),
+        SudoMsg::BeginBlocker {} => {
+            // Expired propositions wait for the 1.x migration to finish
+            if migration_in_progress(deps.storage)? {
+                return Ok(Response::new());
+            }
+            process_expired_propositions(deps, env)
+        }
+        SudoMsg::SetPaused { operation, paused } => set_paused(deps, operation, paused),
+    }
+}
+
+/// Anyone can move an unfinished 1.x migration along, it only rewrites
+/// storage the same way whoever calls it
+pub fn migrate_propositions(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ensure!(
+        migration_in_progress(deps.storage)?,
+        ContractError::Std(StdError::generic_err("No migration in progress"))
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_MIGRATION_LIMIT);
+    let migrated = migrate_propositions_v1(deps.storage, limit)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate_propositions")
+        .add_attribute("migrated_propositions", migrated.to_string())
+        .add_attribute(
+            "migration_done",
+            (!migration_in_progress(deps.storage)?).to_string(),
+        ))
+}
+
+pub fn set_paused(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    operation: Operation,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut flags = PAUSE_FLAGS.may_load(deps.storage)?.unwrap_or_default();
+    flags.set(operation, paused);
+    PAUSE_FLAGS.save(deps.storage, &flags)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_paused")
+        .add_attribute("operation", format!("{operation:?}"))
+        .add_attribute("paused", paused.to_string()))
+}
+
+// Upper bound on propositions settled in a single begin blocker call,
+// anything left over is picked up in the following blocks
+pub const MAX_EXPIRED_PER_BLOCK: usize = 20;
+
+pub fn process_expired_propositions(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+
+    let expired = expired_proposition_ids(
+        deps.storage,
+        PropositionState::Active,
+        now,
+        MAX_EXPIRED_PER_BLOCK,
+    )?;
+    let defaulted = expired_proposition_ids(
+        deps.storage,
+        PropositionState::Accepted,
+        now,
+        MAX_EXPIRED_PER_BLOCK - expired.len(),
+    )?;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let mut events: Vec<Event> = Vec::new();
+
+    for id in expired.iter() {
+        let mut proposition = propositions().load(deps.storage, *id)?;
+        proposition.state = PropositionState::Expired;
+        propositions().save(deps.storage, *id, &proposition)?;
+        clear_private_offers(deps.storage, *id, &proposition);
+        events.push(proposition_event(Transition::Expired, *id, &proposition));
+
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.owner,
+            proposition.owner_escrow(),
+        )?);
+        msgs.extend(refund_fills(deps.storage, *id, &proposition)?);
+        msgs.extend(refund_counter_offers(deps.storage, *id, &proposition)?);
+    }
+
+    // Saving writes the holders of noted sides back to the indexes
+    let config = CONFIG.load(deps.storage)?;
+    for id in defaulted.iter() {
+        let mut proposition = load_loan(deps.storage, &deps.querier, *id)?;
+        proposition.state = PropositionState::Defaulted;
+        events.push(proposition_event(Transition::Defaulted, *id, &proposition));
+
+        // A vault settles at once, else withdrawals would still be priced
+        // as if the loan was to be repaid
+        if proposition.vault.is_some() {
+            if let Some(extension) = proposition.pending_extension.take() {
+                msgs.extend(send_assets_from_contract_to_messages(
+                    &proposition.get_lender(),
+                    vec![extension.extra_premium],
+                )?);
+            }
+            let (claim_msgs, _, fee_msgs) = claim_default(deps.storage, &config, &proposition)?;
+            msgs.extend(claim_msgs);
+            msgs.extend(fee_msgs);
+            proposition.state = PropositionState::Closed;
+            events.push(proposition_event(Transition::Closed, *id, &proposition));
+        }
+        propositions().save(deps.storage, *id, &proposition)?;
     }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_events(events)
+        .add_attribute("method", "begin_blocker")
+        .add_attribute("expired", expired.len().to_string())
+        .add_attribute("defaulted", defaulted.len().to_string()))
+}
+
+fn expired_proposition_ids(
+    storage: &dyn Storage,
+    state: PropositionState,
+    now: u64,
+    limit: usize,
+) -> StdResult<Vec<u64>> {
+    // expired means `now > expiry`, ids start from 1 so (now, 0) excludes `expiry == now`
+    let end = Bound::exclusive((now, 0u64));
+    propositions()
+        .idx
+        .expiry
+        .sub_prefix(state.to_string())
+        .keys(storage, None, Some(end), Order::Ascending)
+        .take(limit)
+        .collect()
 }
 
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
+// Upper bound on propositions a search walks through for a single page
+pub const MAX_SCAN: usize = 300;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<InjectiveQueryWrapper>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetPropositions {
             start_before,
@@ -99,51 +567,426 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         } => {
             let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
             let end = start_before.map(Bound::exclusive);
-            let props: StdResult<Vec<_>> = PROPOSITIONS
+            let props = propositions()
                 .range(deps.storage, None, end, Order::Descending)
                 .take(limit)
-                .collect();
+                .collect::<StdResult<Vec<_>>>()?;
 
-            to_binary(&props.unwrap_or_default())
+            to_binary(&PropositionsResponse::from(props))
         }
         QueryMsg::GetPropositionCount {} => {
             let proposition_count = PROPOSITION_COUNT
                 .may_load(deps.storage)?
                 .unwrap_or_default();
-            to_binary(&format!("{proposition_count}"))
+            to_binary(&CountResponse {
+                count: Uint64::new(proposition_count),
+            })
+        }
+        QueryMsg::GetPayoff { proposition_id } => {
+            let proposition = propositions().load(deps.storage, proposition_id)?;
+            // Only a loan that can still be repaid has a payoff
+            if proposition.state != PropositionState::Accepted {
+                return Err(StdError::generic_err(format!(
+                    "Proposition is {}, not a repayable loan",
+                    proposition.state
+                )));
+            }
+            let principal = proposition.assets.with_amount(proposition.outstanding());
+            to_binary(&Payoff {
+                late_fee: principal.with_amount(proposition.late_fee(&env, principal.amount)),
+                interest: proposition
+                    .assets
+                    .with_amount(proposition.interest_due(env.block.time.seconds())),
+                principal,
+            })
+        }
+        QueryMsg::GetLoanPhase { proposition_id } => {
+            let proposition = propositions().load(deps.storage, proposition_id)?;
+            match proposition.state {
+                PropositionState::Accepted | PropositionState::Defaulted => {
+                    to_binary(&proposition.phase(&env))
+                }
+                state => Err(StdError::generic_err(format!(
+                    "Proposition is {state}, not a running loan"
+                ))),
+            }
+        }
+        QueryMsg::GetFills { proposition_id } => {
+            to_binary(&load_fills(deps.storage, proposition_id)?)
+        }
+        QueryMsg::GetCounterOffers {
+            proposition_id,
+            start_before,
+            limit,
+        } => {
+            let prefix = counter_offers().idx.proposition.prefix(proposition_id);
+            to_binary(&paginate(deps.storage, prefix, start_before, limit)?)
+        }
+        QueryMsg::GetCounterOffersByOwner {
+            owner,
+            start_before,
+            limit,
+        } => {
+            let owner = deps.api.addr_validate(&owner)?;
+            let prefix = counter_offers().idx.owner.prefix(owner);
+            to_binary(&paginate(deps.storage, prefix, start_before, limit)?)
+        }
+        QueryMsg::GetVault { vault_id } => to_binary(&VAULTS.load(deps.storage, vault_id)?),
+        QueryMsg::GetVaultShares { vault_id, owner } => {
+            let owner = deps.api.addr_validate(&owner)?;
+            let shares = VAULT_SHARES
+                .may_load(deps.storage, (vault_id, &owner))?
+                .unwrap_or_default();
+            to_binary(&shares)
+        }
+        QueryMsg::Config {} => to_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::PauseFlags {} => {
+            to_binary(&PAUSE_FLAGS.may_load(deps.storage)?.unwrap_or_default())
         }
         QueryMsg::GetProposition { proposition_id } => {
-            let proposition = PROPOSITIONS.load(deps.storage, proposition_id)?;
-            to_binary(&proposition)
+            let proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+            to_binary(&PropositionResponse {
+                id: proposition_id,
+                proposition,
+            })
+        }
+        QueryMsg::SearchPropositions {
+            filter,
+            start_after,
+            start_before,
+            order,
+            limit,
+        } => to_binary(&search_propositions(
+            deps,
+            *filter,
+            start_after,
+            start_before,
+            order,
+            limit,
+        )?),
+        QueryMsg::GetPropositionsByOwner {
+            owner,
+            start_before,
+            limit,
+        } => {
+            let owner = deps.api.addr_validate(&owner)?;
+            let prefix = propositions().idx.owner.prefix(owner);
+            let props = paginate(deps.storage, prefix, start_before, limit)?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+        QueryMsg::GetPropositionsByContractor {
+            contractor,
+            start_before,
+            limit,
+        } => {
+            let contractor = deps.api.addr_validate(&contractor)?;
+            let prefix = propositions().idx.contractor.prefix(contractor.to_string());
+            let props = paginate(deps.storage, prefix, start_before, limit)?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+        QueryMsg::GetPrivatePropositions {
+            contractor,
+            start_before,
+            limit,
+        } => {
+            let contractor = deps.api.addr_validate(&contractor)?;
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let end = start_before.map(Bound::exclusive);
+            let props = PRIVATE_OFFERS
+                .prefix(&contractor)
+                .keys(deps.storage, None, end, Order::Descending)
+                .take(limit)
+                .map(|id| {
+                    let id = id?;
+                    Ok((id, propositions().load(deps.storage, id)?))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+        QueryMsg::GetPropositionsByState {
+            state,
+            start_before,
+            limit,
+        } => {
+            let prefix = propositions().idx.state.prefix(state.to_string());
+            let props = paginate(deps.storage, prefix, start_before, limit)?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+        QueryMsg::GetPropositionsByDepositDenom {
+            denom,
+            start_before,
+            limit,
+        } => {
+            let prefix = propositions().idx.deposit_denom.prefix(denom);
+            let props = paginate(deps.storage, prefix, start_before, limit)?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+        QueryMsg::GetPropositionsByAssetsDenom {
+            denom,
+            start_before,
+            limit,
+        } => {
+            let prefix = propositions().idx.assets_denom.prefix(denom);
+            let props = paginate(deps.storage, prefix, start_before, limit)?;
+            to_binary(&PropositionsResponse::from(props))
+        }
+    }
+}
+
+fn paginate<T: Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    prefix: Prefix<u64, T, u64>,
+    start_before: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, T)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
+    prefix
+        .range(storage, None, end, Order::Descending)
+        .take(limit)
+        .collect()
+}
+
+fn search_propositions(
+    deps: Deps<InjectiveQueryWrapper>,
+    mut filter: PropositionFilter,
+    start_after: Option<u64>,
+    start_before: Option<u64>,
+    order: Option<SortOrder>,
+    limit: Option<u32>,
+) -> StdResult<PropositionsPage> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = match order.unwrap_or(SortOrder::Descending) {
+        SortOrder::Ascending => Order::Ascending,
+        SortOrder::Descending => Order::Descending,
+    };
+    let min = start_after.map(Bound::exclusive);
+    let max = start_before.map(Bound::exclusive);
+
+    if let Some(owner) = filter.owner.as_deref() {
+        filter.owner = Some(deps.api.addr_validate(owner)?.into_string());
+    }
+    if let Some(contractor) = filter.contractor.as_deref() {
+        filter.contractor = Some(deps.api.addr_validate(contractor)?.into_string());
+    }
+
+    // Narrow the scan with the most selective index available
+    let source = if let Some(owner) = &filter.owner {
+        propositions()
+            .idx
+            .owner
+            .prefix(Addr::unchecked(owner))
+            .range(deps.storage, min, max, order)
+    } else if let Some(contractor) = &filter.contractor {
+        propositions()
+            .idx
+            .contractor
+            .prefix(contractor.clone())
+            .range(deps.storage, min, max, order)
+    } else if let Some(state) = &filter.state {
+        propositions()
+            .idx
+            .state
+            .prefix(state.to_string())
+            .range(deps.storage, min, max, order)
+    } else if let Some(denom) = &filter.deposit_denom {
+        propositions()
+            .idx
+            .deposit_denom
+            .prefix(denom.clone())
+            .range(deps.storage, min, max, order)
+    } else if let Some(denom) = &filter.assets_denom {
+        propositions()
+            .idx
+            .assets_denom
+            .prefix(denom.clone())
+            .range(deps.storage, min, max, order)
+    } else {
+        propositions().range(deps.storage, min, max, order)
+    };
+
+    let mut items: Vec<PropositionResponse> = Vec::new();
+    let mut last_scanned = None;
+    let mut next_cursor = None;
+    for (scanned, item) in source.enumerate() {
+        let (id, proposition) = item?;
+        // Sparse filters stop here, the next page resumes after the last scanned id
+        if scanned == MAX_SCAN {
+            next_cursor = last_scanned;
+            break;
+        }
+        if matches_filter(&filter, &proposition) {
+            // One match past the page tells there is a next one
+            if items.len() == limit {
+                next_cursor = items.last().map(|item| item.id);
+                break;
+            }
+            items.push(PropositionResponse::from((id, proposition)));
+        }
+        last_scanned = Some(id);
+    }
+
+    Ok(PropositionsPage { items, next_cursor })
+}
+
+fn matches_filter(filter: &PropositionFilter, proposition: &Proposition) -> bool {
+    if let Some(proposition_type) = &filter.proposition_type {
+        if *proposition_type != proposition.proposition_type {
+            return false;
+        }
+    }
+    if let Some(state) = &filter.state {
+        if *state != proposition.state {
+            return false;
+        }
+    }
+    if let Some(owner) = &filter.owner {
+        if owner != proposition.owner.as_str() {
+            return false;
+        }
+    }
+    if let Some(contractor) = &filter.contractor {
+        if proposition.contractor.as_ref().map(Addr::as_str) != Some(contractor.as_str()) {
+            return false;
+        }
+    }
+    if let Some(denom) = &filter.deposit_denom {
+        if *denom != proposition.deposit.info.denom() {
+            return false;
+        }
+    }
+    if let Some(denom) = &filter.assets_denom {
+        if *denom != proposition.assets.info.denom() {
+            return false;
         }
     }
+
+    filter.min_premium.unwrap_or_default() <= proposition.premium.amount
+        && filter.max_premium.unwrap_or(Uint128::MAX) >= proposition.premium.amount
+        && filter.min_period.unwrap_or_default() <= proposition.period
+        && filter.max_period.unwrap_or(u64::MAX) >= proposition.period
+}
+
+/// An NFT can only be pawned, as the deposit of an Ask. Deposit, `assets`
+/// and period have to be non-zero and within the configured limits.
+fn validate_terms(
+    limits: &TermLimits,
+    proposition_type: &PropositionType,
+    deposit: &Asset,
+    assets: &Asset,
+    premium: &Asset,
+    period: u64,
+) -> Result<(), ContractError> {
+    ensure!(
+        !assets.info.is_nft()
+            && !premium.info.is_nft()
+            && (!deposit.info.is_nft()
+                || (*proposition_type == PropositionType::Ask && deposit.amount == Uint128::one())),
+        ContractError::InvalidNftCollateral {}
+    );
+
+    for asset in [deposit, assets] {
+        ensure!(
+            !asset.amount.is_zero() && limits.allows_amount(asset),
+            ContractError::InvalidAmount {}
+        );
+    }
+
+    ensure!(
+        period > 0 && limits.allows_period(period),
+        ContractError::InvalidPeriod {}
+    );
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_proposition(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
     proposition_type: PropositionType,
-    deposit: Coin,
-    assets: Coin,
-    premium: Coin,
+    deposit: Asset,
+    assets: Asset,
+    premium: Asset,
     period: u64,
     expiry: u64,
-    contractor: Option<Addr>,
+    contractor: Option<String>,
+    designated_contractors: Option<Vec<String>>,
+    installments: Option<u32>,
+    ltv: Option<LoanToValue>,
+    min_fill: Option<Uint128>,
+    interest_rate: Option<Decimal>,
 ) -> Result<Response, ContractError> {
-    // let funds = &info.funds.clone();
-    let mut balance = NativeBalance(info.funds.clone());
-    if proposition_type == PropositionType::Ask {
-        balance = (balance - deposit.to_owned())?;
-        balance = (balance - premium.to_owned())?;
+    let config = CONFIG.load(deps.storage)?;
+    validate_terms(
+        &config.term_limits,
+        &proposition_type,
+        &deposit,
+        &assets,
+        &premium,
+        period,
+    )?;
+
+    ensure!(
+        expiry > env.block.time.seconds(),
+        ContractError::ExpiryInPast {}
+    );
+
+    let mut designated: Vec<Addr> = Vec::new();
+    for address in contractor
+        .into_iter()
+        .chain(designated_contractors.unwrap_or_default())
+    {
+        let contractor = deps
+            .api
+            .addr_validate(&address)
+            .map_err(|_| ContractError::InvalidAddress { address })?;
+        ensure!(
+            contractor != payment.sender,
+            ContractError::SelfContractor {}
+        );
+        if !designated.contains(&contractor) {
+            designated.push(contractor);
+        }
+    }
+    ensure!(
+        designated.len() <= MAX_DESIGNATED_CONTRACTORS,
+        ContractError::TooManyDesignatedContractors {}
+    );
+
+    // Syndicated Bids are settled per lender, without the single-contractor extras
+    if let Some(min_fill) = min_fill {
+        ensure!(
+            proposition_type == PropositionType::Bid
+                && !min_fill.is_zero()
+                && min_fill <= assets.amount
+                && !deposit.info.is_nft()
+                && designated.is_empty()
+                && installments.is_none()
+                && ltv.is_none(),
+            ContractError::InvalidPartialFill {}
+        );
+    }
+
+    if let Some(rate) = interest_rate {
+        ensure!(
+            !rate.is_zero() && premium.amount.is_zero() && min_fill.is_none(),
+            ContractError::InvalidInterestRate {}
+        );
     }
 
-    if proposition_type == PropositionType::Bid {
-        (balance - assets.to_owned())?;
+    // Only assets with an oracle symbol can be priced
+    if let Some(ltv) = &ltv {
+        ensure!(
+            ltv.is_valid()
+                && config.oracle_symbol(&deposit.info).is_some()
+                && config.oracle_symbol(&assets.info).is_some(),
+            ContractError::InvalidLoanToValue {}
+        );
     }
 
     let proposition = Proposition {
-        owner: info.sender,
+        owner: payment.sender.clone(),
         proposition_type,
         state: PropositionState::Active,
         deposit,
@@ -151,30 +994,62 @@ pub fn create_proposition(
         premium,
         period,
         expiry,
-        contractor,
+        contractor: None,
+        installments: installments.filter(|count| *count > 1),
+        schedule: vec![],
+        repaid: Uint128::zero(),
+        pending_extension: None,
+        ltv,
+        min_fill,
+        filled: Uint128::zero(),
+        vault: None,
+        notes: None,
+        interest_rate,
+        interest_accrued: Uint128::zero(),
+        accrued_at: 0,
+        grace_period: 0,
+        late_fee_bps: 0,
+        designated_contractors: designated,
+        extra_collateral: vec![],
+        pending_substitution: None,
+        substitutions: vec![],
     };
+    ensure!(
+        proposition.has_valid_installments(),
+        ContractError::InvalidInstallments {}
+    );
+
+    let collect_msgs = payment.collect(proposition.owner_escrow(), &env.contract.address)?;
 
     let id = next_id(deps.storage)?;
-    PROPOSITIONS.save(deps.storage, id, &proposition)?;
+    propositions().save(deps.storage, id, &proposition)?;
+    for contractor in proposition.designated_contractors.iter() {
+        PRIVATE_OFFERS.save(deps.storage, (contractor, id), &Empty {})?;
+    }
 
-    Ok(
-        Response::new()
+    Ok(Response::new()
+        .add_messages(collect_msgs)
+        .add_event(proposition_event(Transition::Created, id, &proposition))
         .add_attribute("method", "create_proposition")
-        .add_attribute("proposition_id", id.to_string())
-    )
+        .add_attribute("proposition_id", id.to_string()))
 }
 
 pub fn accept_proposition(
-    deps: DepsMut,
+    deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    info: MessageInfo,
+    payment: Payment,
     proposition_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut proposition = PROPOSITIONS.load(deps.storage, proposition_id)?;
+    let mut proposition = propositions().load(deps.storage, proposition_id)?;
 
-    ensure_ne!(
-        proposition.owner,
-        info.sender,
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_ne!(
+        proposition.owner,
+        payment.sender,
         ContractError::Unauthorized {} // todo: Another error
     );
 
@@ -187,61 +1062,468 @@ pub fn accept_proposition(
         })
     );
 
-    if proposition.contractor.is_some() && proposition.contractor.unwrap() == info.sender {
-        return Err(ContractError::Unauthorized {}); // todo: Another error
-    }
-    proposition.contractor = Some(info.sender);
+    ensure!(
+        proposition.is_open_to(&payment.sender),
+        ContractError::NotDesignatedContractor {}
+    );
+    proposition.contractor = Some(payment.sender.clone());
+    clear_private_offers(deps.storage, proposition_id, &proposition);
+
+    let mut msgs = payment.collect(proposition.contractor_escrow(), &env.contract.address)?;
+    msgs.extend(refund_counter_offers(
+        deps.storage,
+        proposition_id,
+        &proposition,
+    )?);
+
+    let (loan_msgs, fee, treasury) = start_loan(
+        deps.storage,
+        &deps.querier,
+        &env,
+        proposition_id,
+        &mut proposition,
+    )?;
+    msgs.extend(loan_msgs);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(proposition_event(
+            Transition::Accepted,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "accept_proposition")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", treasury)
+        .add_attribute("proposition_type", proposition.proposition_type.to_string())
+        .add_attribute("owner", proposition.owner)
+        .add_attribute("contractor", proposition.contractor.unwrap()))
+}
 
+/// Turns an active proposition into a loan once both sides are escrowed,
+/// `assets` go to the lender and the premium, minus the protocol fee, to the borrower
+fn start_loan(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    env: &Env,
+    proposition_id: u64,
+    proposition: &mut Proposition,
+) -> Result<(Vec<CosmosMsg>, Asset, Addr), ContractError> {
     proposition.expiry = env.block.time.plus_seconds(proposition.period).seconds();
     proposition.state = PropositionState::Accepted;
+    proposition.schedule_installments(env.block.time.seconds())?;
+    proposition.accrued_at = env.block.time.seconds();
+
+    let config = CONFIG.load(storage)?;
+    proposition.grace_period = config.grace_period;
+    proposition.late_fee_bps = config.late_fee_bps;
+    ensure!(
+        proposition
+            .expiry
+            .checked_add(proposition.grace_period)
+            .is_some(),
+        ContractError::InvalidPeriod {}
+    );
+
+    if let (Some(ltv), Some(limits)) =
+        (proposition.current_ltv(querier, &config)?, &proposition.ltv)
+    {
+        ensure!(
+            ltv <= limits.max_ltv,
+            ContractError::LoanToValueExceeded {
+                ltv,
+                max_ltv: limits.max_ltv
+            }
+        );
+    }
+
+    // The right to repayment is tradeable from the start when notes are enabled
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    if let Some(note_contract) = &config.note_contract {
+        let side = proposition.borrower_side();
+        msgs.push(mint_note_msg(
+            note_contract,
+            proposition_id,
+            proposition,
+            side,
+            &proposition.get_borrower(),
+        )?);
+        proposition.notes = Some(Notes {
+            contract: note_contract.clone(),
+            sides: vec![side],
+        });
+    }
+
+    propositions().save(storage, proposition_id, proposition)?;
+
+    msgs.extend(send_assets_from_contract_to_messages(
+        &proposition.get_lender(),
+        vec![proposition.assets.clone()],
+    )?);
+
+    let fee = config.fee(&proposition.premium);
+
+    msgs.extend(send_assets_from_contract_to_messages(
+        &proposition.get_borrower(),
+        vec![proposition
+            .premium
+            .with_amount(proposition.premium.amount - fee.amount)],
+    )?);
+
+    // Kept apart from the payouts so the fee shows up as its own transfer
+    msgs.extend(send_assets_from_contract_to_messages(
+        &config.treasury,
+        vec![fee.clone()],
+    )?);
+
+    Ok((msgs, fee, config.treasury))
+}
+
+// Bounds the payouts done when a syndicated loan starts or defaults
+pub const MAX_FILLS: usize = 30;
+
+pub fn fill_proposition(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut proposition = propositions().load(deps.storage, proposition_id)?;
+
+    ensure!(
+        proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Active);
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    ensure_ne!(
+        proposition.owner,
+        payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    let total = proposition.assets.amount;
+    let filled = proposition.filled + amount;
+    ensure!(
+        !amount.is_zero() && filled <= total,
+        ContractError::InvalidPartialFill {}
+    );
+
+    // Shares are taken as differences of cumulative amounts, so a full
+    // fill adds up to exactly the proposition's deposit and premium
+    let share = |whole: Uint128| {
+        whole.multiply_ratio(filled, total) - whole.multiply_ratio(proposition.filled, total)
+    };
+    let deposit = share(proposition.deposit.amount);
+    let premium = share(proposition.premium.amount);
+
+    let msgs = payment.collect(
+        vec![
+            proposition.deposit.with_amount(deposit),
+            proposition.premium.with_amount(premium),
+        ],
+        &env.contract.address,
+    )?;
+
+    let fill = match FILLS.may_load(deps.storage, (proposition_id, &payment.sender))? {
+        Some(fill) => Fill {
+            amount: fill.amount + amount,
+            deposit: fill.deposit + deposit,
+            premium: fill.premium + premium,
+            ..fill
+        },
+        None => {
+            ensure!(
+                load_fills(deps.storage, proposition_id)?.len() < MAX_FILLS,
+                ContractError::InvalidPartialFill {}
+            );
+            Fill {
+                lender: payment.sender.clone(),
+                amount,
+                deposit,
+                premium,
+            }
+        }
+    };
+    FILLS.save(deps.storage, (proposition_id, &payment.sender), &fill)?;
 
-    PROPOSITIONS.save(deps.storage, proposition_id, &proposition)?;
+    proposition.filled = filled;
 
-    let mut msgs: Vec<BankMsg> = Vec::new();
-    let mut balance = NativeBalance(info.funds.clone());
-    if proposition.proposition_type == PropositionType::Ask {
-        (balance - proposition.assets.clone())?;
+    let mut response = Response::new().add_messages(msgs);
+    if filled == total {
+        let (loan_msgs, fee, treasury) =
+            start_syndicated_loan(deps.storage, &env, proposition_id, &mut proposition)?;
+        response = response
+            .add_messages(loan_msgs)
+            .add_event(proposition_event(
+                Transition::Accepted,
+                proposition_id,
+                &proposition,
+            ))
+            .add_attribute("fee", fee.to_string())
+            .add_attribute("treasury", treasury);
     } else {
-        balance = (balance - proposition.deposit.clone())?;
-        (balance - proposition.premium.clone())?;
+        propositions().save(deps.storage, proposition_id, &proposition)?;
     }
 
-    msgs.push(
-        send_coins_from_contract_to_message(
-            &proposition.get_lender(),
-            [proposition.assets.clone()].to_vec(),
-        )
-        .unwrap(),
+    Ok(response
+        .add_attribute("method", "fill_proposition")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("lender", payment.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("filled", filled)
+        .add_attribute("state", proposition.state.to_string()))
+}
+
+pub fn activate_proposition(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposition = propositions().load(deps.storage, proposition_id)?;
+
+    ensure!(
+        proposition.owner == payment.sender,
+        ContractError::Unauthorized {}
     );
 
-    msgs.push(
-        send_coins_from_contract_to_message(
-            &proposition.get_borrower(),
-            [proposition.premium.clone()].to_vec(),
-        )
-        .unwrap(),
+    ensure_state!(proposition.state, PropositionState::Active);
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    ensure!(
+        proposition
+            .min_fill
+            .is_some_and(|min_fill| proposition.filled >= min_fill),
+        ContractError::InvalidPartialFill {}
     );
 
+    let (msgs, fee, treasury) =
+        start_syndicated_loan(deps.storage, &env, proposition_id, &mut proposition)?;
+
     Ok(Response::new()
         .add_messages(msgs)
-        .add_attribute("method", "accept_proposition")
+        .add_event(proposition_event(
+            Transition::Accepted,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "activate_proposition")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("filled", proposition.filled)
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", treasury))
+}
+
+/// Starts a syndicated Bid on its filled part, every lender gets its share of
+/// `assets` and the owner the unfilled rest plus the premium, minus the protocol fee
+fn start_syndicated_loan(
+    storage: &mut dyn Storage,
+    env: &Env,
+    proposition_id: u64,
+    proposition: &mut Proposition,
+) -> Result<(Vec<CosmosMsg>, Asset, Addr), ContractError> {
+    let fills = load_fills(storage, proposition_id)?;
+    let unfilled = proposition.assets.amount - proposition.filled;
+
+    // From here on the proposition describes the loan actually made
+    proposition.assets.amount = proposition.filled;
+    proposition.deposit.amount = fills.iter().map(|fill| fill.deposit).sum();
+    proposition.premium.amount = fills.iter().map(|fill| fill.premium).sum();
+    proposition.expiry = env.block.time.plus_seconds(proposition.period).seconds();
+    proposition.state = PropositionState::Accepted;
+
+    propositions().save(storage, proposition_id, proposition)?;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    for fill in fills.iter() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &fill.lender,
+            vec![proposition.assets.with_amount(fill.amount)],
+        )?);
+    }
+
+    let config = CONFIG.load(storage)?;
+    let fee = config.fee(&proposition.premium);
+
+    msgs.extend(send_assets_from_contract_to_messages(
+        &proposition.owner,
+        vec![
+            proposition.assets.with_amount(unfilled),
+            proposition
+                .premium
+                .with_amount(proposition.premium.amount - fee.amount),
+        ],
+    )?);
+
+    msgs.extend(send_assets_from_contract_to_messages(
+        &config.treasury,
+        vec![fee.clone()],
+    )?);
+
+    Ok((msgs, fee, config.treasury))
+}
+
+/// Lenders close their own share by repaying it, the owner claims every
+/// share left once the loan is claimable
+fn close_syndicated(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    mut proposition: Proposition,
+) -> Result<Response, ContractError> {
+    if proposition.state != PropositionState::Defaulted {
+        ensure_state!(proposition.state, PropositionState::Accepted);
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut fee = proposition.deposit.with_amount(Uint128::zero());
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+
+    let mut defaulted = false;
+    let own_fill = FILLS.may_load(deps.storage, (proposition_id, &payment.sender))?;
+    match own_fill {
+        Some(fill)
+            if proposition.state == PropositionState::Accepted
+                && proposition.is_not_expired(&env) =>
+        {
+            let repayment = proposition.assets.with_amount(fill.amount);
+            msgs.extend(payment.collect(vec![repayment.clone()], &env.contract.address)?);
+
+            msgs.extend(send_assets_from_contract_to_messages(
+                &proposition.owner,
+                vec![repayment],
+            )?);
+            msgs.extend(send_assets_from_contract_to_messages(
+                &fill.lender,
+                vec![proposition.deposit.with_amount(fill.deposit)],
+            )?);
+
+            proposition.repaid += fill.amount;
+            FILLS.remove(deps.storage, (proposition_id, &fill.lender));
+        }
+        _ => {
+            ensure!(
+                proposition.owner == payment.sender && proposition.is_claimable(&env),
+                ContractError::Unauthorized {}
+            );
+            // Claiming takes no payment
+            msgs.extend(payment.collect(vec![], &env.contract.address)?);
+
+            // Claiming without the begin blocker having flagged it defaults the loan here
+            defaulted = proposition.state == PropositionState::Accepted;
+
+            let fills = load_fills(deps.storage, proposition_id)?;
+            let claimed = proposition
+                .deposit
+                .with_amount(fills.iter().map(|fill| fill.deposit).sum());
+            for fill in fills.iter() {
+                FILLS.remove(deps.storage, (proposition_id, &fill.lender));
+            }
+
+            if config.fee_on_default {
+                fee = config.fee(&claimed);
+            }
+
+            msgs.extend(send_assets_from_contract_to_messages(
+                &proposition.owner,
+                vec![claimed.with_amount(claimed.amount - fee.amount)],
+            )?);
+            msgs.extend(send_assets_from_contract_to_messages(
+                &config.treasury,
+                vec![fee.clone()],
+            )?);
+        }
+    }
+
+    if load_fills(deps.storage, proposition_id)?.is_empty() {
+        proposition.state = PropositionState::Closed;
+    }
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    let mut response = Response::new().add_messages(msgs);
+    if defaulted {
+        response = response.add_event(proposition_event(
+            Transition::Defaulted,
+            proposition_id,
+            &proposition,
+        ));
+    }
+    if proposition.state == PropositionState::Closed {
+        response = response.add_event(proposition_event(
+            Transition::Closed,
+            proposition_id,
+            &proposition,
+        ));
+    }
+
+    Ok(response
+        .add_attribute("method", "close_proposition")
         .add_attribute("id", proposition_id.to_string())
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", config.treasury)
         .add_attribute("proposition_type", proposition.proposition_type.to_string())
         .add_attribute("owner", proposition.owner)
-        .add_attribute("contractor", proposition.contractor.unwrap())
-    )
-        
+        .add_attribute("state", proposition.state.to_string()))
+}
+
+fn load_fills(storage: &dyn Storage, proposition_id: u64) -> StdResult<Vec<Fill>> {
+    FILLS
+        .prefix(proposition_id)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, fill)| fill))
+        .collect()
+}
+
+/// Removes the fills of a syndicated Bid that never started and pays their
+/// deposit and premium back
+fn refund_fills(
+    storage: &mut dyn Storage,
+    proposition_id: u64,
+    proposition: &Proposition,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    for fill in load_fills(storage, proposition_id)? {
+        FILLS.remove(storage, (proposition_id, &fill.lender));
+        msgs.extend(send_assets_from_contract_to_messages(
+            &fill.lender,
+            vec![
+                proposition.deposit.with_amount(fill.deposit),
+                proposition.premium.with_amount(fill.premium),
+            ],
+        )?);
+    }
+
+    Ok(msgs)
 }
 
 pub fn reject_proposition(
-    deps: DepsMut,
+    deps: DepsMut<InjectiveQueryWrapper>,
     _env: Env,
-    info: MessageInfo,
+    payment: Payment,
     proposition_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut proposition = PROPOSITIONS.load(deps.storage, proposition_id)?;
+    let mut proposition = propositions().load(deps.storage, proposition_id)?;
 
-    if proposition.owner != info.sender {
+    if proposition.owner != payment.sender {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -249,100 +1531,1574 @@ pub fn reject_proposition(
 
     proposition.state = PropositionState::Rejected;
 
-    PROPOSITIONS.save(deps.storage, proposition_id, &proposition)?;
-
-    let return_coins: Vec<Coin> = match proposition.proposition_type {
-        PropositionType::Ask => [proposition.deposit.clone(), proposition.premium.clone()].to_vec(),
-        PropositionType::Bid => [proposition.assets.clone()].to_vec(),
-    };
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+    clear_private_offers(deps.storage, proposition_id, &proposition);
 
     Ok(Response::new()
-        .add_message(send_coins_from_contract_to_message(
+        .add_messages(send_assets_from_contract_to_messages(
             &proposition.owner,
-            return_coins,
+            proposition.owner_escrow(),
+        )?)
+        .add_messages(refund_fills(deps.storage, proposition_id, &proposition)?)
+        .add_messages(refund_counter_offers(
+            deps.storage,
+            proposition_id,
+            &proposition,
         )?)
+        .add_event(proposition_event(
+            Transition::Rejected,
+            proposition_id,
+            &proposition,
+        ))
         .add_attribute("method", "reject_proposition"))
 }
 
 pub fn close_proposition(
-    deps: DepsMut,
+    deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    info: MessageInfo,
+    payment: Payment,
     proposition_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut proposition = PROPOSITIONS.load(deps.storage, proposition_id)?;
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
 
-    ensure_state!(proposition.state, PropositionState::Accepted);
+    if proposition.is_syndicated() {
+        return close_syndicated(deps, env, payment, proposition_id, proposition);
+    }
+
+    // Defaulted loans are already past expiry and settle through the claim branch below
+    if proposition.state != PropositionState::Defaulted {
+        ensure_state!(proposition.state, PropositionState::Accepted);
+    }
 
+    let mut events: Vec<Event> = Vec::new();
+    let was_accepted = proposition.state == PropositionState::Accepted;
     proposition.state = PropositionState::Closed;
 
-    let mut bank_msgs: Vec<BankMsg> = Vec::new();
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let mut fee_msgs: Vec<CosmosMsg> = Vec::new();
+    let config = CONFIG.load(deps.storage)?;
+    let fee;
 
-    if proposition.is_not_expired(&env) {
-        ensure!(
-            proposition.get_lender() == info.sender,
-            ContractError::Unauthorized {}
-        );
+    if let Some(extension) = proposition.pending_extension.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            vec![extension.extra_premium],
+        )?);
+    }
+    if let Some(substitution) = proposition.pending_substitution.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            vec![substitution.new_deposit],
+        )?);
+    }
 
-        (NativeBalance(info.funds.clone()) - proposition.assets.clone())?;
+    if payment.sender == proposition.get_lender() && proposition.is_repayable(&env) {
+        let (interest, interest_msgs, interest_fee) =
+            settle_interest(deps.storage, &env, &mut proposition)?;
+        fee = interest_fee;
 
-        bank_msgs.push(
-            send_coins_from_contract_to_message(
-                &proposition.get_borrower(),
-                [proposition.assets.clone()].to_vec(),
-            )
-            .unwrap(),
-        );
+        let outstanding = proposition.assets.with_amount(proposition.outstanding());
+        let late_fee = outstanding.with_amount(proposition.late_fee(&env, outstanding.amount));
+        proposition.repaid = proposition.assets.amount;
 
-        bank_msgs.push(
-            send_coins_from_contract_to_message(
-                &proposition.get_lender(),
-                [proposition.deposit.clone()].to_vec(),
-            )
-            .unwrap(),
-        );
+        // Pull the repayment in before anything is paid out
+        let mut repay_msgs = payment.collect(
+            vec![outstanding.clone(), interest, late_fee.clone()],
+            &env.contract.address,
+        )?;
+        repay_msgs.append(&mut msgs);
+        msgs = repay_msgs;
+
+        msgs.extend(repay_borrower(deps.storage, &proposition, outstanding)?);
+        msgs.extend(interest_msgs);
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_borrower(),
+            vec![late_fee],
+        )?);
+
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            proposition.collateral(),
+        )?);
     } else {
+        // Past expiry, or earlier when an installment was missed. Anyone can
+        // settle a vault's loan, the collateral stays with the vault.
         ensure!(
-            proposition.get_borrower() == info.sender,
+            (proposition.vault.is_some() || proposition.get_borrower() == payment.sender)
+                && proposition.is_claimable(&env),
             ContractError::Unauthorized {}
         );
+        // Claiming takes no payment
+        msgs.extend(payment.collect(vec![], &env.contract.address)?);
 
-        bank_msgs.push(
-            send_coins_from_contract_to_message(
-                &proposition.get_borrower(),
-                [proposition.deposit.clone()].to_vec(),
-            )
-            .unwrap(),
-        );
+        if was_accepted {
+            let mut defaulted = proposition.clone();
+            defaulted.state = PropositionState::Defaulted;
+            events.push(proposition_event(
+                Transition::Defaulted,
+                proposition_id,
+                &defaulted,
+            ));
+        }
+
+        let (claim_msgs, default_fee, default_fee_msgs) =
+            claim_default(deps.storage, &config, &proposition)?;
+        msgs.extend(claim_msgs);
+        fee = default_fee;
+        fee_msgs = default_fee_msgs;
     }
 
-    PROPOSITIONS.save(deps.storage, proposition_id, &proposition)?;
+    propositions().save(deps.storage, proposition_id, &proposition)?;
 
     Ok(Response::new()
-        .add_messages(bank_msgs)
+        .add_messages(msgs)
+        .add_messages(fee_msgs)
+        .add_events(events)
+        .add_event(proposition_event(
+            Transition::Closed,
+            proposition_id,
+            &proposition,
+        ))
         .add_attribute("method", "close_proposition")
         .add_attribute("id", proposition_id.to_string())
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", config.treasury)
         .add_attribute("proposition_type", proposition.proposition_type.to_string())
         .add_attribute("owner", proposition.owner)
-        .add_attribute("contractor", proposition.contractor.unwrap())
-    )
+        .add_attribute("contractor", proposition.contractor.unwrap()))
 }
 
-pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
-    let id: u64 = PROPOSITION_COUNT.may_load(store)?.unwrap_or_default() + 1;
-    PROPOSITION_COUNT.save(store, &id)?;
-    Ok(id)
+pub fn repay_loan(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_lender() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure!(
+        proposition.is_repayable(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    let outstanding = proposition.outstanding();
+    ensure!(
+        !amount.is_zero() && amount <= outstanding,
+        ContractError::InvalidRepayment {
+            amount,
+            outstanding
+        }
+    );
+
+    // Interest due so far is settled with every repayment
+    let (interest, interest_msgs, _) = settle_interest(deps.storage, &env, &mut proposition)?;
+
+    let repayment = proposition.assets.with_amount(amount);
+    let late_fee = repayment.with_amount(proposition.late_fee(&env, amount));
+    let mut msgs = payment.collect(
+        vec![repayment.clone(), interest, late_fee.clone()],
+        &env.contract.address,
+    )?;
+
+    proposition.repaid += amount;
+
+    msgs.extend(repay_borrower(deps.storage, &proposition, repayment)?);
+    msgs.extend(interest_msgs);
+    msgs.extend(send_assets_from_contract_to_messages(
+        &proposition.get_borrower(),
+        vec![late_fee],
+    )?);
+
+    if proposition.outstanding().is_zero() {
+        proposition.state = PropositionState::Closed;
+
+        let mut release = proposition.collateral();
+        if let Some(extension) = proposition.pending_extension.take() {
+            release.push(extension.extra_premium);
+        }
+        if let Some(substitution) = proposition.pending_substitution.take() {
+            release.push(substitution.new_deposit);
+        }
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            release,
+        )?);
+    }
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    let mut response = Response::new().add_messages(msgs);
+    if proposition.state == PropositionState::Closed {
+        response = response.add_event(proposition_event(
+            Transition::Closed,
+            proposition_id,
+            &proposition,
+        ));
+    }
+
+    Ok(response
+        .add_attribute("method", "repay_loan")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("outstanding", proposition.outstanding())
+        .add_attribute("state", proposition.state.to_string()))
 }
 
-pub fn send_coins_from_contract_to_message(
-    to_address: &Addr,
-    coins: Vec<Coin>,
-) -> StdResult<BankMsg> {
-    let mut balance = NativeBalance(coins);
-    balance.normalize();
-
-    Ok(BankMsg::Send {
-        to_address: to_address.to_string(),
-        amount: balance.into_vec(),
-    })
+pub fn request_extension(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    extra_period: u64,
+    extra_premium: Asset,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_lender() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    ensure!(
+        !extra_premium.info.is_nft(),
+        ContractError::InvalidNftCollateral {}
+    );
+
+    // Nobody could approve it on behalf of a vault
+    ensure!(proposition.vault.is_none(), ContractError::InvalidVault {});
+
+    let mut msgs = payment.collect(vec![extra_premium.clone()], &env.contract.address)?;
+
+    if let Some(previous) = proposition.pending_extension.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &payment.sender,
+            vec![previous.extra_premium],
+        )?);
+    }
+
+    proposition.pending_extension = Some(Extension {
+        extra_period,
+        extra_premium,
+    });
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "request_extension")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("extra_period", extra_period.to_string()))
+}
+
+pub fn approve_extension(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_borrower() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    let extension = proposition
+        .pending_extension
+        .take()
+        .ok_or(ContractError::NoPendingExtension {})?;
+
+    proposition.extend(env.block.time.seconds(), extension.extra_period)?;
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(send_assets_from_contract_to_messages(
+            &payment.sender,
+            vec![extension.extra_premium],
+        )?)
+        .add_event(proposition_event(
+            Transition::Extended,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "approve_extension")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("expiry", proposition.expiry.to_string()))
+}
+
+pub fn liquidate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    payment: Payment,
+    proposition_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    let config = CONFIG.load(deps.storage)?;
+    let (ltv, limits) = match (
+        proposition.current_ltv(&deps.querier, &config)?,
+        proposition.ltv.clone(),
+    ) {
+        (Some(ltv), Some(limits)) => (ltv, limits),
+        _ => return Err(ContractError::NotLiquidatable {}),
+    };
+    ensure!(
+        ltv >= limits.liquidation_ltv,
+        ContractError::NotLiquidatable {}
+    );
+
+    proposition.state = PropositionState::Liquidated;
+
+    let bonus = proposition.deposit.amount * limits.liquidation_bonus;
+    let mut msgs = send_assets_from_contract_to_messages(
+        &payment.sender,
+        vec![proposition.deposit.with_amount(bonus)],
+    )?;
+    let mut forfeited = vec![proposition
+        .deposit
+        .with_amount(proposition.deposit.amount - bonus)];
+    forfeited.extend(proposition.extra_collateral.iter().cloned());
+    msgs.extend(settle_default(deps.storage, &proposition, forfeited)?);
+
+    if let Some(extension) = proposition.pending_extension.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            vec![extension.extra_premium],
+        )?);
+    }
+    if let Some(substitution) = proposition.pending_substitution.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &proposition.get_lender(),
+            vec![substitution.new_deposit],
+        )?);
+    }
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(proposition_event(
+            Transition::Liquidated,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "liquidate")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("ltv", ltv.to_string())
+        .add_attribute("liquidator", payment.sender))
+}
+
+pub fn add_collateral(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    // Each lender of a syndicated Bid put in its own share of the deposit
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_lender() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure!(
+        proposition.is_repayable(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    let added = merge_assets(payment.assets.clone());
+    ensure!(!added.is_empty(), ContractError::InvalidAmount {});
+
+    let config = CONFIG.load(deps.storage)?;
+    for asset in added.iter() {
+        ensure!(!asset.info.is_nft(), ContractError::InvalidNftCollateral {});
+
+        if asset.info == proposition.deposit.info {
+            proposition.deposit.amount += asset.amount;
+        } else {
+            ensure!(
+                config.collateral_denoms.contains(&asset.info),
+                ContractError::InvalidCollateral {}
+            );
+            proposition.extra_collateral.push(asset.clone());
+        }
+    }
+    proposition.extra_collateral = merge_assets(proposition.extra_collateral);
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_collateral")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("deposit", proposition.deposit.to_string()))
+}
+
+pub fn withdraw_excess_collateral(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    amount: Uint128,
+    collateral: Option<AssetInfo>,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_lender() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    // Past expiry the whole collateral secures the default
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    // A missed installment already lets the other party claim it all
+    ensure!(
+        !proposition.has_missed_installment(&env),
+        ContractError::InstallmentMissed {}
+    );
+
+    let withdrawn = match collateral {
+        Some(info) if info != proposition.deposit.info => {
+            let extra = proposition
+                .extra_collateral
+                .iter_mut()
+                .find(|extra| extra.info == info)
+                .ok_or(ContractError::InvalidCollateral {})?;
+            ensure!(
+                !amount.is_zero() && amount <= extra.amount,
+                ContractError::InvalidAmount {}
+            );
+            extra.amount -= amount;
+            info
+        }
+        _ => {
+            ensure!(
+                !amount.is_zero() && amount < proposition.deposit.amount,
+                ContractError::InvalidAmount {}
+            );
+            proposition.deposit.amount -= amount;
+            proposition.deposit.info.clone()
+        }
+    };
+    proposition
+        .extra_collateral
+        .retain(|extra| !extra.amount.is_zero());
+
+    let config = CONFIG.load(deps.storage)?;
+    let ratio = proposition
+        .collateral_ratio(&deps.querier, &config)?
+        .ok_or(ContractError::InvalidLoanToValue {})?;
+    let min_ratio = config.min_collateral_ratio;
+    ensure!(
+        !min_ratio.is_zero() && ratio >= min_ratio,
+        ContractError::CollateralRatioBreached { ratio, min_ratio }
+    );
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(send_assets_from_contract_to_messages(
+            &payment.sender,
+            vec![Asset {
+                info: withdrawn,
+                amount,
+            }],
+        )?)
+        .add_attribute("method", "withdraw_excess_collateral")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("ratio", ratio.to_string()))
+}
+
+pub fn substitute_collateral(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    new_deposit: Asset,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_lender() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    ensure!(
+        !new_deposit.amount.is_zero() && new_deposit.info != proposition.deposit.info,
+        ContractError::InvalidCollateral {}
+    );
+
+    // An NFT can only be pawned as the deposit of a new proposition
+    ensure!(
+        !new_deposit.info.is_nft(),
+        ContractError::InvalidNftCollateral {}
+    );
+
+    let config = CONFIG.load(deps.storage)?;
+    // The new deposit has to stay priced for the loan to value
+    ensure!(
+        proposition.ltv.is_none() || config.oracle_symbol(&new_deposit.info).is_some(),
+        ContractError::InvalidLoanToValue {}
+    );
+
+    let mut msgs = payment.collect(vec![new_deposit.clone()], &env.contract.address)?;
+
+    if let Some(previous) = proposition.pending_substitution.take() {
+        msgs.extend(send_assets_from_contract_to_messages(
+            &payment.sender,
+            vec![previous.new_deposit],
+        )?);
+    }
+
+    let substitution = Substitution { new_deposit };
+    let mut response = Response::new();
+
+    if proposition.is_covered_by(&deps.querier, &config, &substitution)? {
+        msgs.extend(swap_deposit(&env, &mut proposition, substitution)?);
+        response = response.add_event(proposition_event(
+            Transition::Substituted,
+            proposition_id,
+            &proposition,
+        ));
+    } else {
+        // Nobody could approve it on behalf of a vault
+        ensure!(proposition.vault.is_none(), ContractError::InvalidVault {});
+        proposition.pending_substitution = Some(substitution);
+    }
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(response
+        .add_messages(msgs)
+        .add_attribute("method", "substitute_collateral")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute(
+            "pending",
+            proposition.pending_substitution.is_some().to_string(),
+        ))
+}
+
+pub fn approve_substitution(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    ensure_state!(proposition.state, PropositionState::Accepted);
+
+    ensure!(
+        proposition.get_borrower() == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    let substitution = proposition
+        .pending_substitution
+        .take()
+        .ok_or(ContractError::NoPendingSubstitution {})?;
+
+    let msgs = swap_deposit(&env, &mut proposition, substitution)?;
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(proposition_event(
+            Transition::Substituted,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "approve_substitution")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("deposit", proposition.deposit.to_string()))
+}
+
+/// Makes the escrowed `new_deposit` the deposit, records the swap and
+/// releases the previous deposit to the party that posted it
+fn swap_deposit(
+    env: &Env,
+    proposition: &mut Proposition,
+    substitution: Substitution,
+) -> StdResult<Vec<CosmosMsg>> {
+    let previous = std::mem::replace(&mut proposition.deposit, substitution.new_deposit);
+
+    proposition.substitutions.push(SubstitutionRecord {
+        previous: previous.clone(),
+        deposit: proposition.deposit.clone(),
+        time: env.block.time.seconds(),
+    });
+
+    send_assets_from_contract_to_messages(&proposition.get_lender(), vec![previous])
+}
+
+pub fn transfer_position(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    proposition_id: u64,
+    side: PositionSide,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    // Defaulted loans still have a deposit to claim
+    if proposition.state != PropositionState::Defaulted {
+        ensure_state!(proposition.state, PropositionState::Accepted);
+    }
+
+    // Noted sides change hands with their note
+    ensure!(!proposition.has_note(side), ContractError::InvalidNote {});
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let lender_side = side == proposition.lender_side();
+
+    let holder = match side {
+        PositionSide::Owner => &mut proposition.owner,
+        PositionSide::Contractor => proposition
+            .contractor
+            .as_mut()
+            .ok_or(ContractError::Unauthorized {})?,
+    };
+    ensure!(*holder == payment.sender, ContractError::Unauthorized {});
+    *holder = recipient.clone();
+
+    // Both sides held by one address would repay itself
+    ensure!(
+        proposition.contractor.as_ref() != Some(&proposition.owner),
+        ContractError::Unauthorized {}
+    );
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+
+    // Pending requests were made by the previous lender, who gets their escrow back
+    if lender_side {
+        if let Some(extension) = proposition.pending_extension.take() {
+            msgs = send_assets_from_contract_to_messages(
+                &payment.sender,
+                vec![extension.extra_premium],
+            )?;
+        }
+        if let Some(substitution) = proposition.pending_substitution.take() {
+            msgs.extend(send_assets_from_contract_to_messages(
+                &payment.sender,
+                vec![substitution.new_deposit],
+            )?);
+        }
+    }
+
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "transfer_position")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("side", side.to_string())
+        .add_attribute("from", payment.sender)
+        .add_attribute("to", recipient))
+}
+
+pub fn mint_note(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    proposition_id: u64,
+    side: PositionSide,
+) -> Result<Response, ContractError> {
+    let mut proposition = load_loan(deps.storage, &deps.querier, proposition_id)?;
+
+    if proposition.state != PropositionState::Defaulted {
+        ensure_state!(proposition.state, PropositionState::Accepted);
+    }
+
+    let holder = match side {
+        PositionSide::Owner => Some(proposition.owner.clone()),
+        PositionSide::Contractor => proposition.contractor.clone(),
+    };
+    ensure!(
+        holder.as_ref() == Some(&payment.sender),
+        ContractError::Unauthorized {}
+    );
+
+    let note_contract = CONFIG
+        .load(deps.storage)?
+        .note_contract
+        .ok_or(ContractError::InvalidNote {})?;
+    let mut notes = proposition.notes.clone().unwrap_or(Notes {
+        contract: note_contract,
+        sides: vec![],
+    });
+    ensure!(!notes.sides.contains(&side), ContractError::InvalidNote {});
+
+    let msg = mint_note_msg(
+        &notes.contract,
+        proposition_id,
+        &proposition,
+        side,
+        &payment.sender,
+    )?;
+    notes.sides.push(side);
+    proposition.notes = Some(notes);
+    propositions().save(deps.storage, proposition_id, &proposition)?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("method", "mint_note")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("side", side.to_string())
+        .add_attribute("token_id", note_token_id(proposition_id, side)))
+}
+
+/// Loads a proposition with the holders of its noted sides brought up to date
+fn load_loan(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    proposition_id: u64,
+) -> StdResult<Proposition> {
+    let mut proposition = propositions().load(storage, proposition_id)?;
+    resolve_note_holders(querier, proposition_id, &mut proposition)?;
+    Ok(proposition)
+}
+
+// Bounds the refunds done when a proposition is taken or rejected
+pub const MAX_COUNTER_OFFERS: usize = 30;
+
+// Smallest share, in basis points, of the proposition's deposit and `assets`
+// a counter-offer can ask for, keeps dust offers from filling the slots
+pub const MIN_COUNTER_OFFER_BPS: u128 = 5_000;
+
+// Bounds the index entries written and removed for a private proposition
+pub const MAX_DESIGNATED_CONTRACTORS: usize = 30;
+
+/// Drops a private proposition from `PRIVATE_OFFERS` once it is no longer open
+fn clear_private_offers(storage: &mut dyn Storage, proposition_id: u64, proposition: &Proposition) {
+    for contractor in proposition.designated_contractors.iter() {
+        PRIVATE_OFFERS.remove(storage, (contractor, proposition_id));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn make_counter_offer(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    proposition_id: u64,
+    deposit: Asset,
+    assets: Asset,
+    premium: Asset,
+    period: u64,
+) -> Result<Response, ContractError> {
+    let proposition = propositions().load(deps.storage, proposition_id)?;
+
+    ensure!(
+        !proposition.is_syndicated(),
+        ContractError::InvalidPartialFill {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Active);
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    ensure_ne!(
+        proposition.owner,
+        payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure!(
+        proposition.is_open_to(&payment.sender),
+        ContractError::NotDesignatedContractor {}
+    );
+
+    let config = CONFIG.load(deps.storage)?;
+    validate_terms(
+        &config.term_limits,
+        &proposition.proposition_type,
+        &deposit,
+        &assets,
+        &premium,
+        period,
+    )?;
+
+    // Only amounts are negotiated, in the proposition's own denoms
+    let is_sized = |offered: &Asset, asked: &Asset| {
+        offered.info == asked.info
+            && offered.amount
+                >= asked
+                    .amount
+                    .multiply_ratio(MIN_COUNTER_OFFER_BPS, MAX_FEE_BPS)
+    };
+    ensure!(
+        is_sized(&deposit, &proposition.deposit)
+            && is_sized(&assets, &proposition.assets)
+            && premium.info == proposition.premium.info,
+        ContractError::InvalidCounterOffer {}
+    );
+
+    // An interest priced loan charges no premium on top, as on creation
+    ensure!(
+        proposition.interest_rate.is_none() || premium.amount.is_zero(),
+        ContractError::InvalidInterestRate {}
+    );
+
+    // One open offer per maker
+    let existing: Vec<(u64, CounterOffer)> = counter_offers()
+        .idx
+        .proposition
+        .prefix(proposition_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    ensure!(
+        existing.len() < MAX_COUNTER_OFFERS
+            && existing
+                .iter()
+                .all(|(_, offer)| offer.owner != payment.sender),
+        ContractError::TooManyCounterOffers {}
+    );
+
+    let offer = CounterOffer {
+        proposition_id,
+        owner: payment.sender.clone(),
+        deposit,
+        assets,
+        premium,
+        period,
+    };
+    // The proposition's installments have to split the offered assets too
+    ensure!(
+        offer.apply_to(&proposition).has_valid_installments(),
+        ContractError::InvalidInstallments {}
+    );
+    let msgs = payment.collect(
+        offer.apply_to(&proposition).contractor_escrow(),
+        &env.contract.address,
+    )?;
+
+    let counter_offer_id = COUNTER_OFFER_COUNT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    COUNTER_OFFER_COUNT.save(deps.storage, &counter_offer_id)?;
+    counter_offers().save(deps.storage, counter_offer_id, &offer)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "make_counter_offer")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("counter_offer_id", counter_offer_id.to_string()))
+}
+
+pub fn accept_counter_offer(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    counter_offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = counter_offers().load(deps.storage, counter_offer_id)?;
+    let proposition_id = offer.proposition_id;
+    let proposition = propositions().load(deps.storage, proposition_id)?;
+
+    ensure!(
+        proposition.owner == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    ensure_state!(proposition.state, PropositionState::Active);
+
+    ensure!(
+        proposition.is_not_expired(&env),
+        ContractError::Std(cosmwasm_std::StdError::GenericErr {
+            msg: "Proposition is expired".to_owned()
+        })
+    );
+
+    // The owner only settles the difference between the old and new escrow
+    let held = proposition.owner_escrow();
+    let mut proposition = offer.apply_to(&proposition);
+    let (missing, excess) = diff_assets(proposition.owner_escrow(), held);
+    let mut msgs = payment.collect(missing, &env.contract.address)?;
+    msgs.extend(send_assets_from_contract_to_messages(
+        &proposition.owner,
+        excess,
+    )?);
+
+    counter_offers().remove(deps.storage, counter_offer_id)?;
+    clear_private_offers(deps.storage, proposition_id, &proposition);
+    msgs.extend(refund_counter_offers(
+        deps.storage,
+        proposition_id,
+        &proposition,
+    )?);
+
+    let (loan_msgs, fee, treasury) = start_loan(
+        deps.storage,
+        &deps.querier,
+        &env,
+        proposition_id,
+        &mut proposition,
+    )?;
+    msgs.extend(loan_msgs);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(proposition_event(
+            Transition::Accepted,
+            proposition_id,
+            &proposition,
+        ))
+        .add_attribute("method", "accept_counter_offer")
+        .add_attribute("id", proposition_id.to_string())
+        .add_attribute("counter_offer_id", counter_offer_id.to_string())
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", treasury)
+        .add_attribute("proposition_type", proposition.proposition_type.to_string())
+        .add_attribute("owner", proposition.owner)
+        .add_attribute("contractor", offer.owner))
+}
+
+pub fn withdraw_counter_offer(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    counter_offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = counter_offers().load(deps.storage, counter_offer_id)?;
+
+    ensure!(
+        offer.owner == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    let proposition = propositions().load(deps.storage, offer.proposition_id)?;
+    counter_offers().remove(deps.storage, counter_offer_id)?;
+
+    Ok(Response::new()
+        .add_messages(send_assets_from_contract_to_messages(
+            &offer.owner,
+            offer.apply_to(&proposition).contractor_escrow(),
+        )?)
+        .add_attribute("method", "withdraw_counter_offer")
+        .add_attribute("counter_offer_id", counter_offer_id.to_string()))
+}
+
+/// Removes every counter-offer still open on `proposition` and pays their escrow back
+fn refund_counter_offers(
+    storage: &mut dyn Storage,
+    proposition_id: u64,
+    proposition: &Proposition,
+) -> StdResult<Vec<CosmosMsg>> {
+    let offers: Vec<(u64, CounterOffer)> = counter_offers()
+        .idx
+        .proposition
+        .prefix(proposition_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    for (id, offer) in offers {
+        counter_offers().remove(storage, id)?;
+        msgs.extend(send_assets_from_contract_to_messages(
+            &offer.owner,
+            offer.apply_to(proposition).contractor_escrow(),
+        )?);
+    }
+
+    Ok(msgs)
+}
+
+pub fn create_vault(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    assets: AssetInfo,
+    terms: VaultTerms,
+) -> Result<Response, ContractError> {
+    ensure!(
+        !assets.is_nft()
+            && !terms.deposit.is_nft()
+            && !terms.collateral_ratio.is_zero()
+            && terms.period > 0,
+        ContractError::InvalidVault {}
+    );
+    // Amounts are bounded on every borrow, as they depend on its size
+    ensure!(
+        CONFIG
+            .load(deps.storage)?
+            .term_limits
+            .allows_period(terms.period),
+        ContractError::InvalidPeriod {}
+    );
+
+    let id = VAULT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    VAULT_COUNT.save(deps.storage, &id)?;
+
+    VAULTS.save(
+        deps.storage,
+        id,
+        &Vault {
+            owner: payment.sender.clone(),
+            assets,
+            terms,
+            total_shares: Uint128::zero(),
+            liquidity: Uint128::zero(),
+            lent: Uint128::zero(),
+            collateral: vec![],
+            collateral_per_share: vec![],
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "create_vault")
+        .add_attribute("vault_id", id.to_string())
+        .add_attribute("owner", payment.sender))
+}
+
+pub fn deposit_to_vault(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    vault_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut vault = VAULTS.load(deps.storage, vault_id)?;
+
+    // A vault wiped out by defaults has shares worth nothing left to price against
+    ensure!(
+        vault.total_shares.is_zero() || !vault.total_assets().is_zero(),
+        ContractError::InvalidVault {}
+    );
+
+    let deposit = Asset {
+        info: vault.assets.clone(),
+        amount,
+    };
+    let mut msgs = payment.collect(vec![deposit], &env.contract.address)?;
+
+    // Shares held so far take their collateral, new ones only share in later defaults
+    let held = VAULT_SHARES
+        .may_load(deps.storage, (vault_id, &payment.sender))?
+        .unwrap_or_default();
+    let owed = claim_vault_collateral(deps.storage, vault_id, &mut vault, &payment.sender, held)?;
+    msgs.extend(send_assets_from_contract_to_messages(
+        &payment.sender,
+        owed,
+    )?);
+
+    let shares = vault.shares_for(amount);
+    ensure!(!shares.is_zero(), ContractError::InvalidVault {});
+
+    vault.total_shares += shares;
+    vault.liquidity += amount;
+    VAULTS.save(deps.storage, vault_id, &vault)?;
+
+    VAULT_SHARES.save(deps.storage, (vault_id, &payment.sender), &(held + shares))?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "deposit_to_vault")
+        .add_attribute("vault_id", vault_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares))
+}
+
+pub fn withdraw_from_vault(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    vault_id: u64,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let mut vault = VAULTS.load(deps.storage, vault_id)?;
+    let held = VAULT_SHARES
+        .may_load(deps.storage, (vault_id, &payment.sender))?
+        .unwrap_or_default();
+
+    ensure!(
+        !shares.is_zero() && shares <= held,
+        ContractError::InvalidVault {}
+    );
+
+    let amount = vault.assets_for(shares);
+    ensure!(
+        amount <= vault.liquidity,
+        ContractError::InsufficientLiquidity {
+            available: vault.liquidity
+        }
+    );
+    // Every held share takes its collateral, not only the withdrawn ones
+    let mut payout =
+        claim_vault_collateral(deps.storage, vault_id, &mut vault, &payment.sender, held)?;
+
+    vault.total_shares -= shares;
+    vault.liquidity -= amount;
+    VAULTS.save(deps.storage, vault_id, &vault)?;
+
+    if held == shares {
+        VAULT_SHARES.remove(deps.storage, (vault_id, &payment.sender));
+        VAULT_CHECKPOINTS.remove(deps.storage, (vault_id, &payment.sender));
+    } else {
+        VAULT_SHARES.save(deps.storage, (vault_id, &payment.sender), &(held - shares))?;
+    }
+
+    payout.push(Asset {
+        info: vault.assets,
+        amount,
+    });
+
+    Ok(Response::new()
+        .add_messages(send_assets_from_contract_to_messages(
+            &payment.sender,
+            merge_assets(payout),
+        )?)
+        .add_attribute("method", "withdraw_from_vault")
+        .add_attribute("vault_id", vault_id.to_string())
+        .add_attribute("shares", shares)
+        .add_attribute("amount", amount))
+}
+
+/// Collateral forfeited to the vault since the checkpoint of `holder` that goes
+/// with its `shares`. Moves the checkpoint up to date.
+fn claim_vault_collateral(
+    storage: &mut dyn Storage,
+    vault_id: u64,
+    vault: &mut Vault,
+    holder: &Addr,
+    shares: Uint128,
+) -> StdResult<Vec<Asset>> {
+    let checkpoint = VAULT_CHECKPOINTS
+        .may_load(storage, (vault_id, holder))?
+        .unwrap_or_default();
+    let owed = vault.collateral_owed(shares, &checkpoint);
+    let (_, remaining) = diff_assets(owed.clone(), std::mem::take(&mut vault.collateral));
+    vault.collateral = remaining;
+    VAULT_CHECKPOINTS.save(storage, (vault_id, holder), &vault.collateral_per_share)?;
+    Ok(owed)
+}
+
+/// Opens an accepted Ask on the vault's terms, with the contract standing in
+/// as contractor. The premium, less the protocol fee, goes to the vault's liquidity.
+pub fn borrow_from_vault(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    payment: Payment,
+    vault_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut vault = VAULTS.load(deps.storage, vault_id)?;
+
+    ensure!(
+        !amount.is_zero() && amount <= vault.liquidity,
+        ContractError::InsufficientLiquidity {
+            available: vault.liquidity
+        }
+    );
+
+    let deposit = Asset {
+        info: vault.terms.deposit.clone(),
+        amount: amount * vault.terms.collateral_ratio,
+    };
+    let assets = Asset {
+        info: vault.assets.clone(),
+        amount,
+    };
+    let premium = assets.with_amount(amount * vault.terms.premium_rate);
+
+    let config = CONFIG.load(deps.storage)?;
+    validate_terms(
+        &config.term_limits,
+        &PropositionType::Ask,
+        &deposit,
+        &assets,
+        &premium,
+        vault.terms.period,
+    )?;
+
+    let mut msgs = payment.collect(
+        vec![deposit.clone(), premium.clone()],
+        &env.contract.address,
+    )?;
+
+    let fee = config.fee(&premium);
+
+    vault.liquidity = vault.liquidity - amount + premium.amount - fee.amount;
+    vault.lent += amount;
+    VAULTS.save(deps.storage, vault_id, &vault)?;
+
+    let id = next_id(deps.storage)?;
+    let proposition = Proposition {
+        owner: payment.sender.clone(),
+        proposition_type: PropositionType::Ask,
+        state: PropositionState::Accepted,
+        deposit,
+        assets: assets.clone(),
+        premium,
+        period: vault.terms.period,
+        expiry: env.block.time.plus_seconds(vault.terms.period).seconds(),
+        contractor: Some(env.contract.address.clone()),
+        installments: None,
+        schedule: vec![],
+        repaid: Uint128::zero(),
+        pending_extension: None,
+        ltv: None,
+        min_fill: None,
+        filled: Uint128::zero(),
+        vault: Some(vault_id),
+        notes: None,
+        interest_rate: None,
+        interest_accrued: Uint128::zero(),
+        accrued_at: 0,
+        grace_period: 0,
+        late_fee_bps: 0,
+        designated_contractors: vec![],
+        extra_collateral: vec![],
+        pending_substitution: None,
+        substitutions: vec![],
+    };
+    propositions().save(deps.storage, id, &proposition)?;
+
+    msgs.extend(send_assets_from_contract_to_messages(
+        &payment.sender,
+        vec![assets],
+    )?);
+    msgs.extend(send_assets_from_contract_to_messages(
+        &config.treasury,
+        vec![fee.clone()],
+    )?);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(proposition_event(Transition::Accepted, id, &proposition))
+        .add_attribute("method", "borrow_from_vault")
+        .add_attribute("vault_id", vault_id.to_string())
+        .add_attribute("proposition_id", id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("treasury", config.treasury))
+}
+
+/// Checkpoints the interest of an accepted loan and pays all of it, less the
+/// protocol fee, to the party that provided `assets`. Returns the interest to
+/// collect, its payouts and the fee.
+fn settle_interest(
+    storage: &dyn Storage,
+    env: &Env,
+    proposition: &mut Proposition,
+) -> StdResult<(Asset, Vec<CosmosMsg>, Asset)> {
+    proposition.accrue_interest(env.block.time.seconds());
+    let interest = proposition.assets.with_amount(proposition.interest_accrued);
+    proposition.interest_accrued = Uint128::zero();
+
+    let config = CONFIG.load(storage)?;
+    let fee = config.fee(&interest);
+
+    let mut msgs = send_assets_from_contract_to_messages(
+        &proposition.get_borrower(),
+        vec![interest.with_amount(interest.amount - fee.amount)],
+    )?;
+    msgs.extend(send_assets_from_contract_to_messages(
+        &config.treasury,
+        vec![fee.clone()],
+    )?);
+
+    Ok((interest, msgs, fee))
+}
+
+/// Pays `repayment` to the party that provided `assets`, a vault gets it
+/// back into its liquidity
+fn repay_borrower(
+    storage: &mut dyn Storage,
+    proposition: &Proposition,
+    repayment: Asset,
+) -> StdResult<Vec<CosmosMsg>> {
+    match proposition.vault {
+        Some(vault_id) => {
+            let mut vault = VAULTS.load(storage, vault_id)?;
+            vault.lent = vault.lent.checked_sub(repayment.amount)?;
+            vault.liquidity += repayment.amount;
+            VAULTS.save(storage, vault_id, &vault)?;
+            Ok(vec![])
+        }
+        None => send_assets_from_contract_to_messages(&proposition.get_borrower(), vec![repayment]),
+    }
+}
+
+/// Pays the `forfeited` collateral of a loan that is not repaid to the party
+/// that provided `assets`. A vault writes the outstanding part off its share
+/// price and splits the collateral over its current shares, or leaves it to
+/// the vault owner once no shares are left.
+fn settle_default(
+    storage: &mut dyn Storage,
+    proposition: &Proposition,
+    forfeited: Vec<Asset>,
+) -> StdResult<Vec<CosmosMsg>> {
+    match proposition.vault {
+        Some(vault_id) => {
+            let mut vault = VAULTS.load(storage, vault_id)?;
+            vault.lent = vault.lent.checked_sub(proposition.outstanding())?;
+            let accrued = vault.accrue_collateral(forfeited.clone())?;
+            VAULTS.save(storage, vault_id, &vault)?;
+            if accrued {
+                Ok(vec![])
+            } else {
+                send_assets_from_contract_to_messages(&vault.owner, forfeited)
+            }
+        }
+        None => send_assets_from_contract_to_messages(&proposition.get_borrower(), forfeited),
+    }
+}
+
+/// Takes the protocol fee off the deposit of a loan that is not repaid, when
+/// charged on defaults, and forfeits the rest of its collateral. Returns the
+/// payouts, the fee and its payout.
+fn claim_default(
+    storage: &mut dyn Storage,
+    config: &Config,
+    proposition: &Proposition,
+) -> StdResult<(Vec<CosmosMsg>, Asset, Vec<CosmosMsg>)> {
+    let mut fee = proposition.deposit.with_amount(Uint128::zero());
+    let mut fee_msgs = vec![];
+    if config.fee_on_default {
+        fee = config.fee(&proposition.deposit);
+        fee_msgs = send_assets_from_contract_to_messages(&config.treasury, vec![fee.clone()])?;
+    }
+
+    let mut forfeited = vec![proposition
+        .deposit
+        .with_amount(proposition.deposit.amount - fee.amount)];
+    forfeited.extend(proposition.extra_collateral.iter().cloned());
+    let msgs = settle_default(storage, proposition, forfeited)?;
+    Ok((msgs, fee, fee_msgs))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    payment: Payment,
+    owner: Option<String>,
+    treasury: Option<String>,
+    fee_bps: Option<u16>,
+    fee_on_default: Option<bool>,
+    note_contract: Option<String>,
+    grace_period: Option<u64>,
+    late_fee_bps: Option<u16>,
+    funds_mode: Option<FundsMode>,
+    term_limits: Option<TermLimits>,
+    collateral_denoms: Option<Vec<AssetInfo>>,
+    min_collateral_ratio: Option<Decimal>,
+    token_contracts: Option<Vec<String>>,
+    oracle_symbols: Option<Vec<(AssetInfo, String)>>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    ensure!(
+        config.owner == payment.sender,
+        ContractError::Unauthorized {}
+    );
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_validate(&owner)?;
+    }
+    if let Some(treasury) = treasury {
+        config.treasury = deps.api.addr_validate(&treasury)?;
+    }
+    if let Some(fee_bps) = fee_bps {
+        ensure!(
+            u128::from(fee_bps) <= MAX_FEE_BPS,
+            ContractError::InvalidFee {}
+        );
+        config.fee_bps = fee_bps;
+    }
+    if let Some(fee_on_default) = fee_on_default {
+        config.fee_on_default = fee_on_default;
+    }
+    if let Some(note_contract) = note_contract {
+        config.note_contract = Some(deps.api.addr_validate(&note_contract)?);
+    }
+    if let Some(grace_period) = grace_period {
+        ensure!(
+            grace_period <= MAX_GRACE_PERIOD,
+            ContractError::InvalidPeriod {}
+        );
+        config.grace_period = grace_period;
+    }
+    if let Some(late_fee_bps) = late_fee_bps {
+        ensure!(
+            u128::from(late_fee_bps) <= MAX_FEE_BPS,
+            ContractError::InvalidFee {}
+        );
+        config.late_fee_bps = late_fee_bps;
+    }
+    if let Some(funds_mode) = funds_mode {
+        config.funds_mode = funds_mode;
+    }
+    if let Some(term_limits) = term_limits {
+        // Upper bounds have to be allowed themselves, else nothing is
+        ensure!(
+            term_limits
+                .max_period
+                .is_none_or(|max| term_limits.allows_period(max)),
+            ContractError::InvalidPeriod {}
+        );
+        ensure!(
+            term_limits
+                .amounts
+                .iter()
+                .all(|limit| !limit.info.is_nft() && limit.max.is_none_or(|max| limit.allows(max))),
+            ContractError::InvalidAmount {}
+        );
+        config.term_limits = term_limits;
+    }
+    if let Some(collateral_denoms) = collateral_denoms {
+        // NFTs cannot be topped up, nor split when withdrawn
+        ensure!(
+            collateral_denoms.iter().all(|info| !info.is_nft()),
+            ContractError::InvalidNftCollateral {}
+        );
+        config.collateral_denoms = collateral_denoms;
+    }
+    if let Some(min_collateral_ratio) = min_collateral_ratio {
+        config.min_collateral_ratio = min_collateral_ratio;
+    }
+    if let Some(token_contracts) = token_contracts {
+        config.token_contracts = token_contracts
+            .iter()
+            .map(|token| deps.api.addr_validate(token))
+            .collect::<StdResult<_>>()?;
+    }
+    if let Some(oracle_symbols) = oracle_symbols {
+        // NFTs have no oracle price
+        ensure!(
+            oracle_symbols.iter().all(|(info, _)| !info.is_nft()),
+            ContractError::InvalidLoanToValue {}
+        );
+        config.oracle_symbols = oracle_symbols;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_config")
+        .add_attribute("owner", config.owner)
+        .add_attribute("treasury", config.treasury)
+        .add_attribute("fee_bps", config.fee_bps.to_string()))
+}
+
+pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
+    let id: u64 = PROPOSITION_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    PROPOSITION_COUNT.save(store, &id)?;
+    Ok(id)
+}
+
+/// Native coins go out in a single `BankMsg`, every CW20 token in its own transfer
+pub fn send_assets_from_contract_to_messages(
+    to_address: &Addr,
+    assets: Vec<Asset>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut coins = NativeBalance::default();
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+
+    for asset in merge_assets(assets) {
+        match asset.info {
+            AssetInfo::Native(denom) => {
+                coins += Coin {
+                    denom,
+                    amount: asset.amount,
+                }
+            }
+            AssetInfo::Cw20(_) | AssetInfo::Cw721 { .. } => {
+                msgs.push(asset.transfer_msg(to_address)?)
+            }
+        }
+    }
+
+    coins.normalize();
+    if !coins.is_empty() {
+        msgs.insert(
+            0,
+            BankMsg::Send {
+                to_address: to_address.to_string(),
+                amount: coins.into_vec(),
+            }
+            .into(),
+        );
+    }
+
+    Ok(msgs)
 }
@@ -1,19 +1,90 @@
-use cosmwasm_std::{Coin, StdError};
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use thiserror::Error;
 
-use crate::msg::PropositionState;
+use crate::asset::Asset;
+use crate::msg::{Operation, PropositionState};
 
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
+    #[error("{0}")]
+    SemVer(#[from] semver::Error),
     #[error("Unauthorized")]
     Unauthorized {},
     #[error("PaymentError")]
     PaymentError {
-        expected: Coin,
-        actual: Option<Coin>,
+        expected: Vec<Asset>,
+        actual: Vec<Asset>,
+    },
+    #[error("InvalidRepayment")]
+    InvalidRepayment {
+        amount: Uint128,
+        outstanding: Uint128,
+    },
+    #[error("InvalidReceiveMsg")]
+    InvalidReceiveMsg {},
+    #[error("UntrustedToken")]
+    UntrustedToken {},
+    #[error("InvalidNftCollateral")]
+    InvalidNftCollateral {},
+    #[error("InvalidInstallments")]
+    InvalidInstallments {},
+    #[error("InvalidAmount")]
+    InvalidAmount {},
+    #[error("InvalidPeriod")]
+    InvalidPeriod {},
+    #[error("ExpiryInPast")]
+    ExpiryInPast {},
+    #[error("SelfContractor")]
+    SelfContractor {},
+    #[error("NotDesignatedContractor")]
+    NotDesignatedContractor {},
+    #[error("TooManyDesignatedContractors")]
+    TooManyDesignatedContractors {},
+    #[error("InvalidAddress")]
+    InvalidAddress { address: String },
+    #[error("CannotMigrate")]
+    CannotMigrate {
+        previous_contract: String,
+        previous_version: String,
     },
+    #[error("Paused")]
+    Paused { operation: Operation },
+    #[error("InvalidFee")]
+    InvalidFee {},
+    #[error("InvalidLoanToValue")]
+    InvalidLoanToValue {},
+    #[error("LoanToValueExceeded")]
+    LoanToValueExceeded { ltv: Decimal, max_ltv: Decimal },
+    #[error("InvalidCollateral")]
+    InvalidCollateral {},
+    #[error("CollateralRatioBreached")]
+    CollateralRatioBreached { ratio: Decimal, min_ratio: Decimal },
+    #[error("NotLiquidatable")]
+    NotLiquidatable {},
+    #[error("InvalidPartialFill")]
+    InvalidPartialFill {},
+    #[error("InvalidInterestRate")]
+    InvalidInterestRate {},
+    #[error("InvalidNote")]
+    InvalidNote {},
+    #[error("InvalidVault")]
+    InvalidVault {},
+    #[error("InsufficientLiquidity")]
+    InsufficientLiquidity { available: Uint128 },
+    #[error("TooManyCounterOffers")]
+    TooManyCounterOffers {},
+    #[error("InvalidCounterOffer")]
+    InvalidCounterOffer {},
+    #[error("NoPendingExtension")]
+    NoPendingExtension {},
+    #[error("NoPendingSubstitution")]
+    NoPendingSubstitution {},
+    #[error("InstallmentMissed")]
+    InstallmentMissed {},
+    #[error("MigrationInProgress")]
+    MigrationInProgress {},
     #[error("WrongPropositionStatus")]
     WrongPropositionStatus {
         expected: PropositionState,
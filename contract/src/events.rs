@@ -0,0 +1,87 @@
+use cosmwasm_std::Event;
+
+use crate::asset::{Asset, AssetInfo};
+use crate::state::Proposition;
+
+/// Emitted as `wasm-crypto_pawn` once wasmd prefixes it
+pub const EVENT_TYPE: &str = "crypto_pawn";
+
+/// Bumped whenever an attribute is renamed, removed or changes meaning
+pub const EVENT_VERSION: &str = "1";
+
+/// State transitions of a proposition, reported in the `action` attribute
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    Created,
+    Accepted,
+    Rejected,
+    Expired,
+    Extended,
+    Substituted,
+    Closed,
+    Defaulted,
+    Liquidated,
+}
+
+impl Transition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transition::Created => "created",
+            Transition::Accepted => "accepted",
+            Transition::Rejected => "rejected",
+            Transition::Expired => "expired",
+            Transition::Extended => "extended",
+            Transition::Substituted => "substituted",
+            Transition::Closed => "closed",
+            Transition::Defaulted => "defaulted",
+            Transition::Liquidated => "liquidated",
+        }
+    }
+}
+
+/// Snapshot of the proposition after `transition`, carrying its full terms
+/// so that indexers never have to query the contract
+pub fn proposition_event(
+    transition: Transition,
+    proposition_id: u64,
+    proposition: &Proposition,
+) -> Event {
+    let mut event = Event::new(EVENT_TYPE)
+        .add_attribute("version", EVENT_VERSION)
+        .add_attribute("action", transition.as_str())
+        .add_attribute("proposition_id", proposition_id.to_string())
+        .add_attribute("proposition_type", proposition.proposition_type.to_string())
+        .add_attribute("state", proposition.state.to_string())
+        .add_attribute("owner", proposition.owner.as_str());
+
+    // Attribute values may not be empty, so an unaccepted proposition has no contractor
+    if let Some(contractor) = &proposition.contractor {
+        event = event.add_attribute("contractor", contractor.as_str());
+    }
+
+    event = add_asset(event, "deposit", &proposition.deposit);
+    event = add_asset(event, "assets", &proposition.assets);
+    event = add_asset(event, "premium", &proposition.premium);
+
+    if let Some(interest_rate) = proposition.interest_rate {
+        event = event.add_attribute("interest_rate", interest_rate.to_string());
+    }
+
+    event
+        .add_attribute("period", proposition.period.to_string())
+        .add_attribute("expiry", proposition.expiry.to_string())
+        .add_attribute("repaid", proposition.repaid)
+}
+
+fn add_asset(event: Event, name: &str, asset: &Asset) -> Event {
+    let event = event
+        .add_attribute(format!("{name}_denom"), asset.info.denom())
+        .add_attribute(format!("{name}_amount"), asset.amount);
+
+    match &asset.info {
+        AssetInfo::Cw721 { token_id, .. } => {
+            event.add_attribute(format!("{name}_token_id"), token_id)
+        }
+        _ => event,
+    }
+}
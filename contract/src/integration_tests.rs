@@ -1,16 +1,115 @@
 #[cfg(test)]
 mod tests {
+    use std::fmt::Debug;
+
     use crate::helpers::CwTemplateContract;
     use crate::msg::InstantiateMsg;
-    use cosmwasm_std::{Addr, Coin, Empty, Uint128};
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use crate::oracle::{
+        InjectiveQuery, InjectiveQueryWrapper, OraclePriceResponse, PricePairState,
+    };
+    use anyhow::{bail, Result as AnyResult};
+    use cosmwasm_std::testing::{MockApi, MockStorage};
+    use cosmwasm_std::{
+        to_binary, Addr, Api, Binary, BlockInfo, Coin, CustomQuery, Decimal, Empty, Querier,
+        Storage, Uint128,
+    };
+    use cw_multi_test::{
+        AppResponse, BankKeeper, BasicAppBuilder, Contract, ContractWrapper, CosmosRouter,
+        Executor, Module, WasmKeeper,
+    };
+    use cw_storage_plus::Map;
+    use schemars::JsonSchema;
+    use serde::de::DeserializeOwned;
+
+    type App = cw_multi_test::App<
+        BankKeeper,
+        MockApi,
+        MockStorage,
+        OracleMock,
+        WasmKeeper<Empty, InjectiveQueryWrapper>,
+    >;
+
+    const ORACLE_PRICES: Map<(&str, &str), Decimal> = Map::new("oracle_prices");
+
+    /// Stands in for the Injective oracle module, prices are set with `set_price`
+    pub struct OracleMock {}
+
+    impl OracleMock {
+        pub fn set_price(
+            &self,
+            storage: &mut dyn Storage,
+            base: &str,
+            quote: &str,
+            price: Decimal,
+        ) {
+            ORACLE_PRICES.save(storage, (base, quote), &price).unwrap();
+        }
+    }
+
+    impl Module for OracleMock {
+        type ExecT = Empty;
+        type QueryT = InjectiveQueryWrapper;
+        type SudoT = Empty;
+
+        fn execute<ExecC, QueryC>(
+            &self,
+            _api: &dyn Api,
+            _storage: &mut dyn Storage,
+            _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+            _block: &BlockInfo,
+            _sender: Addr,
+            _msg: Self::ExecT,
+        ) -> AnyResult<AppResponse>
+        where
+            ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+            QueryC: CustomQuery + DeserializeOwned + 'static,
+        {
+            bail!("Oracle mock does not execute messages")
+        }
+
+        fn sudo<ExecC, QueryC>(
+            &self,
+            _api: &dyn Api,
+            _storage: &mut dyn Storage,
+            _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+            _block: &BlockInfo,
+            _msg: Self::SudoT,
+        ) -> AnyResult<AppResponse>
+        where
+            ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+            QueryC: CustomQuery + DeserializeOwned + 'static,
+        {
+            bail!("Oracle mock does not handle sudo")
+        }
+
+        fn query(
+            &self,
+            _api: &dyn Api,
+            storage: &dyn Storage,
+            _querier: &dyn Querier,
+            _block: &BlockInfo,
+            request: Self::QueryT,
+        ) -> AnyResult<Binary> {
+            match request.query_data {
+                InjectiveQuery::OraclePrice { base, quote, .. } => {
+                    let pair_price = ORACLE_PRICES.may_load(storage, (&base, &quote))?;
+                    Ok(to_binary(&OraclePriceResponse {
+                        price_pair_state: pair_price
+                            .map(|pair_price| PricePairState { pair_price }),
+                    })?)
+                }
+            }
+        }
+    }
 
-    pub fn contract_template() -> Box<dyn Contract<Empty>> {
+    pub fn contract_template() -> Box<dyn Contract<Empty, InjectiveQueryWrapper>> {
         let contract = ContractWrapper::new(
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
-        );
+        )
+        .with_sudo(crate::contract::sudo)
+        .with_migrate(crate::contract::migrate);
         Box::new(contract)
     }
 
@@ -43,29 +142,39 @@ mod tests {
             },
         ];
 
-        AppBuilder::new().build(|router, _, storage| {
-            router
-                .bank
-                .init_balance(storage, &Addr::unchecked(ALICE), init_coins_balance.clone())
-                .unwrap();
+        BasicAppBuilder::<Empty, InjectiveQueryWrapper>::new_custom()
+            .with_custom(OracleMock {})
+            .build(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(ALICE), init_coins_balance.clone())
+                    .unwrap();
 
-            router
-                .bank
-                .init_balance(storage, &Addr::unchecked(BOB), init_coins_balance.clone())
-                .unwrap();
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(BOB), init_coins_balance.clone())
+                    .unwrap();
 
-            router
-                .bank
-                .init_balance(storage, &Addr::unchecked(ADMIN), init_coins_balance.clone())
-                .unwrap();
-        })
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(ADMIN), init_coins_balance.clone())
+                    .unwrap();
+            })
     }
 
     fn proper_instantiate() -> (App, CwTemplateContract) {
+        instantiate_with(InstantiateMsg {
+            owner: None,
+            treasury: ADMIN.to_string(),
+            fee_bps: 0,
+            fee_on_default: false,
+        })
+    }
+
+    fn instantiate_with(msg: InstantiateMsg) -> (App, CwTemplateContract) {
         let mut app = mock_app();
         let cw_template_id = app.store_code(contract_template());
 
-        let msg = InstantiateMsg {};
         let cw_template_contract_addr = app
             .instantiate_contract(
                 cw_template_id,
@@ -82,15 +191,93 @@ mod tests {
         (app, cw_template_contract)
     }
 
+    /// Bank balance of the address in the given denom
+    fn balance(app: &App, address: &str, denom: &str) -> Uint128 {
+        app.wrap().query_balance(address, denom).unwrap().amount
+    }
+
+    /// The proposition most tests set up, the first one created
+    fn proposition(
+        app: &App,
+        cw_template_contract: &CwTemplateContract,
+    ) -> crate::state::Proposition {
+        app.wrap()
+            .query_wasm_smart::<crate::msg::PropositionResponse>(
+                cw_template_contract.addr(),
+                &crate::msg::QueryMsg::GetProposition { proposition_id: 1 },
+            )
+            .unwrap()
+            .proposition
+    }
+
+    /// Lets the contract take hooks from the given token contracts
+    fn trust_tokens(
+        app: &mut App,
+        cw_template_contract: &CwTemplateContract,
+        token_contracts: Vec<String>,
+    ) {
+        let cosmos_msg = cw_template_contract
+            .call(crate::msg::ExecuteMsg::UpdateConfig {
+                owner: None,
+                treasury: None,
+                fee_bps: None,
+                fee_on_default: None,
+                note_contract: None,
+                grace_period: None,
+                late_fee_bps: None,
+                funds_mode: None,
+                term_limits: None,
+                collateral_denoms: None,
+                min_collateral_ratio: None,
+                token_contracts: Some(token_contracts),
+                oracle_symbols: None,
+            })
+            .unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+    }
+
+    /// Prices the given native denoms on the oracle under their own name
+    fn set_oracle_symbols(
+        app: &mut App,
+        cw_template_contract: &CwTemplateContract,
+        denoms: &[&str],
+    ) {
+        let cosmos_msg = cw_template_contract
+            .call(crate::msg::ExecuteMsg::UpdateConfig {
+                owner: None,
+                treasury: None,
+                fee_bps: None,
+                fee_on_default: None,
+                note_contract: None,
+                grace_period: None,
+                late_fee_bps: None,
+                funds_mode: None,
+                term_limits: None,
+                collateral_denoms: None,
+                min_collateral_ratio: None,
+                token_contracts: None,
+                oracle_symbols: Some(
+                    denoms
+                        .iter()
+                        .map(|denom| {
+                            (
+                                crate::asset::AssetInfo::Native(denom.to_string()),
+                                denom.to_string(),
+                            )
+                        })
+                        .collect(),
+                ),
+            })
+            .unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+    }
+
     mod proposition_ask_test {
 
         use cosmwasm_std::{coin, Timestamp};
 
         use super::*;
-        use crate::{
-            state::Proposition,
-            msg::{ExecuteMsg, QueryMsg},
-        };
+        use crate::msg::{ExecuteMsg, PropositionsResponse, QueryMsg};
 
         fn init_test_case() -> (App, CwTemplateContract) {
             let (mut app, cw_template_contract) = proper_instantiate();
@@ -100,18 +287,26 @@ mod tests {
                 deposit: Coin {
                     denom: INJ_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 assets: Coin {
                     denom: ATOM_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 premium: Coin {
                     denom: USDT_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 period: 100,
                 expiry: app.block_info().time.seconds() + 100,
                 contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
             };
 
             let cosmos_msg = cw_template_contract
@@ -137,7 +332,7 @@ mod tests {
                 start_before: None,
                 limit: None,
             };
-            let _result = app.wrap().query_wasm_smart::<Vec<(u64, Proposition)>>(
+            let _result = app.wrap().query_wasm_smart::<PropositionsResponse>(
                 cw_template_contract.addr().clone(),
                 &msg,
             );
@@ -385,10 +580,7 @@ mod tests {
         use cosmwasm_std::Timestamp;
 
         use super::*;
-        use crate::{
-            state::Proposition,
-            msg::{ExecuteMsg, QueryMsg},
-        };
+        use crate::msg::{ExecuteMsg, PropositionsResponse, QueryMsg};
 
         fn init_test_case() -> (App, CwTemplateContract) {
             let (mut app, cw_template_contract) = proper_instantiate();
@@ -398,18 +590,26 @@ mod tests {
                 deposit: Coin {
                     denom: INJ_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 assets: Coin {
                     denom: ATOM_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 premium: Coin {
                     denom: USDT_DENOM.to_string(),
                     amount: Uint128::new(100),
-                },
+                }
+                .into(),
                 period: 100,
                 expiry: app.block_info().time.seconds() + 100,
                 contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
             };
 
             let cosmos_msg = cw_template_contract
@@ -429,7 +629,7 @@ mod tests {
                 start_before: None,
                 limit: None,
             };
-            let _result = app.wrap().query_wasm_smart::<Vec<(u64, Proposition)>>(
+            let _result = app.wrap().query_wasm_smart::<PropositionsResponse>(
                 cw_template_contract.addr().clone(),
                 &msg,
             );
@@ -799,4 +999,4653 @@ mod tests {
             );
         }
     }
+
+    mod proposition_index_test {
+
+        use super::*;
+        use crate::contract::MAX_SCAN;
+        use crate::msg::{
+            ExecuteMsg, PropositionFilter, PropositionState, PropositionType, PropositionsPage,
+            PropositionsResponse, QueryMsg, SortOrder,
+        };
+
+        fn create(
+            app: &mut App,
+            contract: &CwTemplateContract,
+            sender: &str,
+            proposition_type: PropositionType,
+            funds: Vec<Coin>,
+        ) {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type,
+                deposit: Coin {
+                    denom: INJ_DENOM.to_string(),
+                    amount: Uint128::new(100),
+                }
+                .into(),
+                assets: Coin {
+                    denom: ATOM_DENOM.to_string(),
+                    amount: Uint128::new(100),
+                }
+                .into(),
+                premium: Coin {
+                    denom: USDT_DENOM.to_string(),
+                    amount: Uint128::new(100),
+                }
+                .into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+
+            let cosmos_msg = contract.call_with_funds(msg, funds).unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).unwrap();
+        }
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            create(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                PropositionType::Ask,
+                vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+            );
+            create(
+                &mut app,
+                &cw_template_contract,
+                BOB,
+                PropositionType::Bid,
+                vec![Coin::new(100, ATOM_DENOM)],
+            );
+            create(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                PropositionType::Bid,
+                vec![Coin::new(100, ATOM_DENOM)],
+            );
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 2 },
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn query_ids(app: &App, contract: &CwTemplateContract, msg: QueryMsg) -> Vec<u64> {
+            app.wrap()
+                .query_wasm_smart::<PropositionsResponse>(contract.addr(), &msg)
+                .unwrap()
+                .propositions
+                .into_iter()
+                .map(|item| item.id)
+                .collect()
+        }
+
+        #[test]
+        fn test_by_owner() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::GetPropositionsByOwner {
+                owner: ALICE.to_string(),
+                start_before: None,
+                limit: None,
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![3, 1]);
+
+            let msg = QueryMsg::GetPropositionsByOwner {
+                owner: ALICE.to_string(),
+                start_before: Some(3),
+                limit: None,
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![1]);
+        }
+
+        #[test]
+        fn test_by_contractor() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::GetPropositionsByContractor {
+                contractor: ADMIN.to_string(),
+                start_before: None,
+                limit: None,
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![2]);
+        }
+
+        #[test]
+        fn test_by_state() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::GetPropositionsByState {
+                state: PropositionState::Active,
+                start_before: None,
+                limit: Some(1),
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![3]);
+
+            let msg = QueryMsg::GetPropositionsByState {
+                state: PropositionState::Accepted,
+                start_before: None,
+                limit: None,
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![2]);
+        }
+
+        #[test]
+        fn test_by_denoms() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::GetPropositionsByDepositDenom {
+                denom: INJ_DENOM.to_string(),
+                start_before: None,
+                limit: None,
+            };
+            assert_eq!(query_ids(&app, &cw_template_contract, msg), vec![3, 2, 1]);
+
+            let msg = QueryMsg::GetPropositionsByAssetsDenom {
+                denom: SOL_DENOM.to_string(),
+                start_before: None,
+                limit: None,
+            };
+            assert!(query_ids(&app, &cw_template_contract, msg).is_empty());
+        }
+
+        fn search(
+            app: &App,
+            contract: &CwTemplateContract,
+            msg: QueryMsg,
+        ) -> (Vec<u64>, Option<u64>) {
+            let page: PropositionsPage =
+                app.wrap().query_wasm_smart(contract.addr(), &msg).unwrap();
+            (
+                page.items.into_iter().map(|item| item.id).collect(),
+                page.next_cursor,
+            )
+        }
+
+        #[test]
+        fn test_search_ascending_pages() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    proposition_type: Some(PropositionType::Bid),
+                    ..Default::default()
+                }),
+                start_after: None,
+                start_before: None,
+                order: Some(SortOrder::Ascending),
+                limit: Some(1),
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![2], Some(2)));
+
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    proposition_type: Some(PropositionType::Bid),
+                    ..Default::default()
+                }),
+                start_after: Some(2),
+                start_before: None,
+                order: Some(SortOrder::Ascending),
+                limit: Some(5),
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![3], None));
+
+            // A page ending on the last match has no next one
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    proposition_type: Some(PropositionType::Bid),
+                    ..Default::default()
+                }),
+                start_after: Some(2),
+                start_before: None,
+                order: Some(SortOrder::Ascending),
+                limit: Some(1),
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![3], None));
+        }
+
+        #[test]
+        fn test_search_filters() {
+            let (app, cw_template_contract) = init_test_case();
+
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    owner: Some(ALICE.to_string()),
+                    state: Some(PropositionState::Active),
+                    min_premium: Some(Uint128::new(100)),
+                    max_period: Some(100),
+                    ..Default::default()
+                }),
+                start_after: None,
+                start_before: None,
+                order: None,
+                limit: None,
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![3, 1], None));
+
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    deposit_denom: Some(INJ_DENOM.to_string()),
+                    min_period: Some(101),
+                    ..Default::default()
+                }),
+                start_after: None,
+                start_before: None,
+                order: None,
+                limit: None,
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![], None));
+
+            let msg = QueryMsg::SearchPropositions {
+                filter: Box::default(),
+                start_after: Some(1),
+                start_before: Some(3),
+                order: Some(SortOrder::Descending),
+                limit: None,
+            };
+            assert_eq!(search(&app, &cw_template_contract, msg), (vec![2], None));
+        }
+
+        #[test]
+        fn test_search_bounds_scan() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            for _ in 0..MAX_SCAN {
+                let msg = ExecuteMsg::CreateProposition {
+                    proposition_type: PropositionType::Ask,
+                    deposit: Coin::new(1, INJ_DENOM).into(),
+                    assets: Coin::new(1, ATOM_DENOM).into(),
+                    premium: Coin::new(1, USDT_DENOM).into(),
+                    period: 100,
+                    expiry: app.block_info().time.seconds() + 100,
+                    contractor: None,
+                    designated_contractors: None,
+                    installments: None,
+                    ltv: None,
+                    min_fill: None,
+                    interest_rate: None,
+                };
+                let cosmos_msg = cw_template_contract
+                    .call_with_funds(msg, vec![Coin::new(1, INJ_DENOM), Coin::new(1, USDT_DENOM)])
+                    .unwrap();
+                app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+            }
+
+            // Nothing matches within the scan bound, the cursor skips past it
+            let search_from = |start_before| QueryMsg::SearchPropositions {
+                filter: Box::new(PropositionFilter {
+                    min_premium: Some(Uint128::new(100)),
+                    ..Default::default()
+                }),
+                start_after: None,
+                start_before,
+                order: None,
+                limit: None,
+            };
+            assert_eq!(
+                search(&app, &cw_template_contract, search_from(None)),
+                (vec![], Some(4))
+            );
+            assert_eq!(
+                search(&app, &cw_template_contract, search_from(Some(4))),
+                (vec![3, 2, 1], None)
+            );
+        }
+    }
+
+    mod begin_blocker_test {
+
+        use super::*;
+        use crate::{
+            contract::MAX_EXPIRED_PER_BLOCK,
+            msg::{
+                ExecuteMsg, PropositionResponse, PropositionState, PropositionType, QueryMsg,
+                SudoMsg,
+            },
+        };
+
+        fn create_ask(app: &mut App, contract: &CwTemplateContract, amount: u128) {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(amount, INJ_DENOM).into(),
+                assets: Coin::new(amount, ATOM_DENOM).into(),
+                premium: Coin::new(amount, USDT_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = contract
+                .call_with_funds(
+                    msg,
+                    vec![Coin::new(amount, INJ_DENOM), Coin::new(amount, USDT_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+        }
+
+        fn state_of(app: &App, contract: &CwTemplateContract, id: u64) -> PropositionState {
+            app.wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: id },
+                )
+                .unwrap()
+                .proposition
+                .state
+        }
+
+        fn begin_block(app: &mut App, contract: &CwTemplateContract, seconds: u64) {
+            app.update_block(|block| block.time = block.time.plus_seconds(seconds));
+            app.wasm_sudo(contract.addr(), &SudoMsg::BeginBlocker {})
+                .unwrap();
+        }
+
+        #[test]
+        fn test_active_expires_and_refunds() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create_ask(&mut app, &cw_template_contract, 100);
+
+            begin_block(&mut app, &cw_template_contract, 100);
+            assert_eq!(
+                state_of(&app, &cw_template_contract, 1),
+                PropositionState::Active
+            );
+
+            begin_block(&mut app, &cw_template_contract, 1);
+            assert_eq!(
+                state_of(&app, &cw_template_contract, 1),
+                PropositionState::Expired
+            );
+
+            for denom in [INJ_DENOM, USDT_DENOM] {
+                assert_eq!(
+                    app.wrap()
+                        .query_balance(Addr::unchecked(ALICE), denom)
+                        .unwrap()
+                        .amount,
+                    Uint128::new(1000)
+                );
+            }
+        }
+
+        #[test]
+        fn test_accepted_defaults_and_is_claimable() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create_ask(&mut app, &cw_template_contract, 100);
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            begin_block(&mut app, &cw_template_contract, 101);
+            assert_eq!(
+                state_of(&app, &cw_template_contract, 1),
+                PropositionState::Defaulted
+            );
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(
+                state_of(&app, &cw_template_contract, 1),
+                PropositionState::Closed
+            );
+            assert_eq!(
+                app.wrap()
+                    .query_balance(Addr::unchecked(BOB), INJ_DENOM)
+                    .unwrap()
+                    .amount,
+                Uint128::new(1100)
+            );
+        }
+
+        #[test]
+        fn test_work_is_bounded_per_block() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            let total = MAX_EXPIRED_PER_BLOCK as u64 + 5;
+            for _ in 0..total {
+                create_ask(&mut app, &cw_template_contract, 1);
+            }
+
+            begin_block(&mut app, &cw_template_contract, 101);
+            let expired = (1..=total)
+                .filter(|id| {
+                    state_of(&app, &cw_template_contract, *id) == PropositionState::Expired
+                })
+                .count();
+            assert_eq!(expired, MAX_EXPIRED_PER_BLOCK);
+
+            begin_block(&mut app, &cw_template_contract, 1);
+            for id in 1..=total {
+                assert_eq!(
+                    state_of(&app, &cw_template_contract, id),
+                    PropositionState::Expired
+                );
+            }
+        }
+    }
+
+    mod repayment_test {
+
+        use super::*;
+        use crate::msg::{ExecuteMsg, PropositionState, PropositionType};
+
+        fn accepted_loan(installments: Option<u32>) -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(100, USDT_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    msg,
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn repay(app: &mut App, contract: &CwTemplateContract, amount: u128) -> bool {
+            let cosmos_msg = contract
+                .call_with_funds(
+                    ExecuteMsg::RepayLoan {
+                        proposition_id: 1,
+                        amount: Uint128::new(amount),
+                    },
+                    vec![Coin::new(amount, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).is_ok()
+        }
+
+        #[test]
+        fn test_partial_repayments() {
+            let (mut app, cw_template_contract) = accepted_loan(None);
+
+            assert!(repay(&mut app, &cw_template_contract, 30));
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.state, PropositionState::Accepted);
+            assert_eq!(loan.outstanding(), Uint128::new(70));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(930));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(900));
+
+            assert!(!repay(&mut app, &cw_template_contract, 71));
+
+            assert!(repay(&mut app, &cw_template_contract, 70));
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.state, PropositionState::Closed);
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+            assert_eq!(
+                balance(&app, cw_template_contract.addr().as_str(), INJ_DENOM),
+                Uint128::zero()
+            );
+        }
+
+        #[test]
+        fn test_close_pays_only_outstanding() {
+            let (mut app, cw_template_contract) = accepted_loan(None);
+
+            assert!(repay(&mut app, &cw_template_contract, 40));
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(60, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_installment_schedule() {
+            let (app, cw_template_contract) = accepted_loan(Some(4));
+            let start = app.block_info().time.seconds();
+
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.schedule.len(), 4);
+            assert_eq!(loan.schedule[0].due, start + 25);
+            assert_eq!(loan.schedule[3].due, loan.expiry);
+            assert_eq!(
+                loan.schedule
+                    .iter()
+                    .map(|installment| installment.amount)
+                    .sum::<Uint128>(),
+                Uint128::new(100)
+            );
+        }
+
+        #[test]
+        fn test_missed_installment_is_claimable_early() {
+            let (mut app, cw_template_contract) = accepted_loan(Some(4));
+            let close = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(26));
+            assert!(repay(&mut app, &cw_template_contract, 25));
+            assert!(app.execute(Addr::unchecked(BOB), close.clone()).is_err());
+
+            app.update_block(|block| block.time = block.time.plus_seconds(25));
+            app.execute(Addr::unchecked(BOB), close).unwrap();
+
+            assert_eq!(
+                proposition(&app, &cw_template_contract).state,
+                PropositionState::Closed
+            );
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1100));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(925));
+        }
+
+        #[test]
+        fn test_more_installments_than_assets() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(100, USDT_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: Some(101),
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    msg,
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+        }
+    }
+
+    mod extension_test {
+
+        use super::*;
+        use crate::msg::{ExecuteMsg, PropositionType};
+
+        // Alice owns the proposition, Bob accepts it
+        fn accepted_loan(
+            proposition_type: PropositionType,
+            installments: Option<u32>,
+        ) -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let (create_funds, accept_funds) = match proposition_type {
+                PropositionType::Ask => (
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+                    vec![Coin::new(100, ATOM_DENOM)],
+                ),
+                PropositionType::Bid => (
+                    vec![Coin::new(100, ATOM_DENOM)],
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(100, USDT_DENOM)],
+                ),
+            };
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(100, USDT_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, create_funds)
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    accept_funds,
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn request(app: &mut App, contract: &CwTemplateContract, sender: &str, premium: u128) {
+            let cosmos_msg = contract
+                .call_with_funds(
+                    ExecuteMsg::RequestExtension {
+                        proposition_id: 1,
+                        extra_period: 50,
+                        extra_premium: Coin::new(premium, USDT_DENOM).into(),
+                    },
+                    vec![Coin::new(premium, USDT_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).unwrap();
+        }
+
+        fn approve(app: &mut App, contract: &CwTemplateContract, sender: &str) -> bool {
+            let cosmos_msg = contract
+                .call(ExecuteMsg::ApproveExtension { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).is_ok()
+        }
+
+        #[test]
+        fn test_ask_extension() {
+            let (mut app, cw_template_contract) = accepted_loan(PropositionType::Ask, None);
+            let expiry = proposition(&app, &cw_template_contract).expiry;
+
+            request(&mut app, &cw_template_contract, ALICE, 10);
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(890));
+            assert!(!approve(&mut app, &cw_template_contract, ALICE));
+            assert!(approve(&mut app, &cw_template_contract, BOB));
+
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.expiry, expiry + 50);
+            assert_eq!(loan.period, 150);
+            assert!(loan.pending_extension.is_none());
+            assert_eq!(balance(&app, BOB, USDT_DENOM), Uint128::new(1110));
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            let close = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(BOB), close).is_err());
+        }
+
+        #[test]
+        fn test_bid_extension() {
+            let (mut app, cw_template_contract) = accepted_loan(PropositionType::Bid, None);
+
+            request(&mut app, &cw_template_contract, BOB, 10);
+            assert!(!approve(&mut app, &cw_template_contract, BOB));
+            assert!(approve(&mut app, &cw_template_contract, ALICE));
+
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(1110));
+            assert_eq!(balance(&app, BOB, USDT_DENOM), Uint128::new(890));
+        }
+
+        #[test]
+        fn test_new_request_refunds_previous() {
+            let (mut app, cw_template_contract) = accepted_loan(PropositionType::Ask, None);
+
+            request(&mut app, &cw_template_contract, ALICE, 10);
+            request(&mut app, &cw_template_contract, ALICE, 20);
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(880));
+
+            let close = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), close).unwrap();
+
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(900));
+            assert_eq!(
+                balance(&app, cw_template_contract.addr().as_str(), USDT_DENOM),
+                Uint128::zero()
+            );
+            assert!(!approve(&mut app, &cw_template_contract, BOB));
+        }
+
+        #[test]
+        fn test_extension_moves_installments() {
+            let (mut app, cw_template_contract) = accepted_loan(PropositionType::Ask, Some(2));
+            let start = app.block_info().time.seconds();
+
+            request(&mut app, &cw_template_contract, ALICE, 10);
+            assert!(approve(&mut app, &cw_template_contract, BOB));
+
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.schedule[0].due, start + 100);
+            assert_eq!(loan.schedule[1].due, loan.expiry);
+
+            // The first installment was due at 50 before the extension
+            app.update_block(|block| block.time = block.time.plus_seconds(60));
+            let close = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(BOB), close).is_err());
+        }
+    }
+
+    mod cw20_test {
+
+        use cosmwasm_std::to_binary;
+        use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+
+        use super::*;
+        use crate::{
+            asset::Asset,
+            msg::{ExecuteMsg, PropositionType},
+            ContractError,
+        };
+
+        fn cw20_template() -> Box<dyn Contract<Empty, InjectiveQueryWrapper>> {
+            let contract = ContractWrapper::new_with_empty(
+                cw20_base::contract::execute,
+                cw20_base::contract::instantiate,
+                cw20_base::contract::query,
+            );
+            Box::new(contract)
+        }
+
+        fn init_test_case() -> (App, CwTemplateContract, Addr) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cw20_id = app.store_code(cw20_template());
+            let token = app
+                .instantiate_contract(
+                    cw20_id,
+                    Addr::unchecked(ADMIN),
+                    &cw20_base::msg::InstantiateMsg {
+                        name: "Pawn Token".to_string(),
+                        symbol: "PAWN".to_string(),
+                        decimals: 6,
+                        initial_balances: vec![
+                            Cw20Coin {
+                                address: ALICE.to_string(),
+                                amount: Uint128::new(1000),
+                            },
+                            Cw20Coin {
+                                address: BOB.to_string(),
+                                amount: Uint128::new(1000),
+                            },
+                        ],
+                        mint: None,
+                        marketing: None,
+                    },
+                    &[],
+                    "pawn token",
+                    None,
+                )
+                .unwrap();
+            trust_tokens(&mut app, &cw_template_contract, vec![token.to_string()]);
+
+            (app, cw_template_contract, token)
+        }
+
+        fn token_balance(app: &App, token: &Addr, address: &str) -> u128 {
+            app.wrap()
+                .query_wasm_smart::<BalanceResponse>(
+                    token,
+                    &Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap()
+                .balance
+                .u128()
+        }
+
+        fn send(
+            app: &mut App,
+            token: &Addr,
+            contract: &CwTemplateContract,
+            sender: &str,
+            amount: u128,
+            msg: &ExecuteMsg,
+        ) -> bool {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                token.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: contract.addr().to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(msg).unwrap(),
+                },
+                &[],
+            )
+            .is_ok()
+        }
+
+        fn create_ask(app: &App, token: &Addr) -> ExecuteMsg {
+            ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Asset::cw20(100u128, token.clone()),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Asset::cw20(10u128, token.clone()),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            }
+        }
+
+        #[test]
+        fn test_ask_with_cw20_collateral() {
+            let (mut app, cw_template_contract, token) = init_test_case();
+
+            let msg = create_ask(&app, &token);
+            assert!(!send(
+                &mut app,
+                &token,
+                &cw_template_contract,
+                ALICE,
+                100,
+                &msg
+            ));
+            assert!(send(
+                &mut app,
+                &token,
+                &cw_template_contract,
+                ALICE,
+                110,
+                &msg
+            ));
+            assert_eq!(token_balance(&app, &token, ALICE), 890);
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+            assert_eq!(token_balance(&app, &token, BOB), 1010);
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(token_balance(&app, &token, ALICE), 990);
+            assert_eq!(
+                token_balance(&app, &token, cw_template_contract.addr().as_str()),
+                0
+            );
+        }
+
+        #[test]
+        fn test_cw20_pulled_from_allowance() {
+            let (mut app, cw_template_contract, token) = init_test_case();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Bid,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Asset::cw20(100u128, token.clone()),
+                premium: Asset::cw20(10u128, token.clone()),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            assert!(send(
+                &mut app,
+                &token,
+                &cw_template_contract,
+                ALICE,
+                100,
+                &msg
+            ));
+
+            // Premium is not part of the native funds, so it comes out of the allowance
+            let accept = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, INJ_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(BOB), accept.clone()).is_err());
+
+            app.execute_contract(
+                Addr::unchecked(BOB),
+                token.clone(),
+                &Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: cw_template_contract.addr().to_string(),
+                    amount: Uint128::new(10),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute(Addr::unchecked(BOB), accept).unwrap();
+
+            assert_eq!(token_balance(&app, &token, ALICE), 910);
+            assert_eq!(token_balance(&app, &token, BOB), 1090);
+            assert_eq!(
+                app.wrap()
+                    .query_balance(cw_template_contract.addr(), INJ_DENOM)
+                    .unwrap()
+                    .amount,
+                Uint128::new(100)
+            );
+        }
+
+        // Calls the hook directly, as any contract can, reporting `sender`
+        fn hook(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            caller: &Addr,
+            sender: &str,
+            msg: &ExecuteMsg,
+        ) -> ContractError {
+            app.execute_contract(
+                caller.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                    sender: sender.to_string(),
+                    amount: Uint128::new(110),
+                    msg: to_binary(msg).unwrap(),
+                }),
+                &[],
+            )
+            .unwrap_err()
+            .downcast()
+            .unwrap()
+        }
+
+        #[test]
+        fn test_hooks_only_from_trusted_tokens() {
+            let (mut app, cw_template_contract, token) = init_test_case();
+
+            let msg = create_ask(&app, &token);
+            assert!(matches!(
+                hook(
+                    &mut app,
+                    &cw_template_contract,
+                    &Addr::unchecked(BOB),
+                    ALICE,
+                    &msg
+                ),
+                ContractError::UntrustedToken {}
+            ));
+
+            trust_tokens(&mut app, &cw_template_contract, vec![]);
+            assert!(!send(
+                &mut app,
+                &token,
+                &cw_template_contract,
+                ALICE,
+                110,
+                &msg
+            ));
+            assert_eq!(token_balance(&app, &token, ALICE), 1000);
+        }
+
+        #[test]
+        fn test_hooks_only_carry_payments() {
+            let (mut app, cw_template_contract, token) = init_test_case();
+
+            // The reported sender must not stand in for the owner
+            let msg = ExecuteMsg::UpdateConfig {
+                owner: Some(ALICE.to_string()),
+                treasury: None,
+                fee_bps: None,
+                fee_on_default: None,
+                note_contract: None,
+                grace_period: None,
+                late_fee_bps: None,
+                funds_mode: None,
+                term_limits: None,
+                collateral_denoms: None,
+                min_collateral_ratio: None,
+                token_contracts: None,
+                oracle_symbols: None,
+            };
+            assert!(matches!(
+                hook(&mut app, &cw_template_contract, &token, ADMIN, &msg),
+                ContractError::InvalidReceiveMsg {}
+            ));
+        }
+    }
+
+    mod cw721_test {
+
+        use cosmwasm_std::{to_binary, Timestamp};
+        use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse};
+
+        use super::*;
+        use crate::{
+            asset::Asset,
+            msg::{ExecuteMsg, PropositionType},
+            ContractError,
+        };
+
+        const TOKEN_ID: &str = "punk-1";
+
+        fn cw721_template() -> Box<dyn Contract<Empty, InjectiveQueryWrapper>> {
+            let contract = ContractWrapper::new_with_empty(
+                cw721_base::entry::execute,
+                cw721_base::entry::instantiate,
+                cw721_base::entry::query,
+            );
+            Box::new(contract)
+        }
+
+        fn init_test_case() -> (App, CwTemplateContract, Addr) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cw721_id = app.store_code(cw721_template());
+            let nft = app
+                .instantiate_contract(
+                    cw721_id,
+                    Addr::unchecked(ADMIN),
+                    &cw721_base::InstantiateMsg {
+                        name: "Pawn Punks".to_string(),
+                        symbol: "PUNK".to_string(),
+                        minter: ADMIN.to_string(),
+                    },
+                    &[],
+                    "pawn punks",
+                    None,
+                )
+                .unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                nft.clone(),
+                &cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Mint(
+                    cw721_base::MintMsg {
+                        token_id: TOKEN_ID.to_string(),
+                        owner: ALICE.to_string(),
+                        token_uri: None,
+                        extension: None,
+                    },
+                ),
+                &[],
+            )
+            .unwrap();
+            trust_tokens(&mut app, &cw_template_contract, vec![nft.to_string()]);
+
+            (app, cw_template_contract, nft)
+        }
+
+        fn nft_owner(app: &App, nft: &Addr) -> String {
+            app.wrap()
+                .query_wasm_smart::<OwnerOfResponse>(
+                    nft,
+                    &Cw721QueryMsg::OwnerOf {
+                        token_id: TOKEN_ID.to_string(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap()
+                .owner
+        }
+
+        fn create_msg(
+            app: &App,
+            nft: &Addr,
+            proposition_type: PropositionType,
+            premium: u128,
+        ) -> ExecuteMsg {
+            ExecuteMsg::CreateProposition {
+                proposition_type,
+                deposit: Asset::cw721(nft.clone(), TOKEN_ID),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(premium, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            }
+        }
+
+        fn accept(app: &mut App, cw_template_contract: &CwTemplateContract) {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+        }
+
+        fn send_nft(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            nft: &Addr,
+            msg: &ExecuteMsg,
+        ) -> AnyResult<AppResponse> {
+            app.execute_contract(
+                Addr::unchecked(ALICE),
+                nft.clone(),
+                &Cw721ExecuteMsg::SendNft {
+                    contract: cw_template_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                    msg: to_binary(msg).unwrap(),
+                },
+                &[],
+            )
+        }
+
+        #[test]
+        fn test_pawn_sent_nft_and_close() {
+            let (mut app, cw_template_contract, nft) = init_test_case();
+
+            app.execute_contract(
+                Addr::unchecked(ALICE),
+                nft.clone(),
+                &Cw721ExecuteMsg::Approve {
+                    spender: cw_template_contract.addr().to_string(),
+                    token_id: TOKEN_ID.to_string(),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+            // An approval is not enough, the NFT has to be sent
+            let cosmos_msg = cw_template_contract
+                .call(create_msg(&app, &nft, PropositionType::Ask, 0))
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap_err();
+            assert_eq!(nft_owner(&app, &nft), ALICE);
+
+            let msg = create_msg(&app, &nft, PropositionType::Ask, 0);
+            send_nft(&mut app, &cw_template_contract, &nft, &msg).unwrap();
+            assert_eq!(
+                nft_owner(&app, &nft),
+                cw_template_contract.addr().to_string()
+            );
+
+            accept(&mut app, &cw_template_contract);
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(nft_owner(&app, &nft), ALICE);
+        }
+
+        #[test]
+        fn test_escrowed_nft_cannot_be_claimed() {
+            let (mut app, cw_template_contract, nft) = init_test_case();
+
+            let msg = create_msg(&app, &nft, PropositionType::Ask, 0);
+            send_nft(&mut app, &cw_template_contract, &nft, &msg).unwrap();
+
+            // Pawning the NFT the contract holds must not move it
+            let cosmos_msg = cw_template_contract
+                .call(create_msg(&app, &nft, PropositionType::Ask, 0))
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap_err();
+
+            // Nor may anyone but the collection vouch for its sender
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(BOB),
+                    cw_template_contract.addr(),
+                    &ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                        sender: BOB.to_string(),
+                        token_id: TOKEN_ID.to_string(),
+                        msg: to_binary(&msg).unwrap(),
+                    }),
+                    &[],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::UntrustedToken {}
+            ));
+
+            assert_eq!(
+                nft_owner(&app, &nft),
+                cw_template_contract.addr().to_string()
+            );
+        }
+
+        #[test]
+        fn test_sent_nft_goes_to_contractor_on_default() {
+            let (mut app, cw_template_contract, nft) = init_test_case();
+
+            let msg = create_msg(&app, &nft, PropositionType::Ask, 0);
+            send_nft(&mut app, &cw_template_contract, &nft, &msg).unwrap();
+            assert_eq!(
+                nft_owner(&app, &nft),
+                cw_template_contract.addr().to_string()
+            );
+
+            accept(&mut app, &cw_template_contract);
+
+            app.update_block(|f| f.time = Timestamp::from_seconds(9999999999));
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(nft_owner(&app, &nft), BOB);
+        }
+
+        #[test]
+        fn test_nft_only_as_ask_deposit() {
+            let (mut app, cw_template_contract, nft) = init_test_case();
+
+            let msg = create_msg(&app, &nft, PropositionType::Bid, 0);
+            assert!(send_nft(&mut app, &cw_template_contract, &nft, &msg).is_err());
+            assert_eq!(nft_owner(&app, &nft), ALICE);
+        }
+    }
+
+    mod ltv_test {
+
+        use super::*;
+        use crate::{
+            msg::{ExecuteMsg, PropositionResponse, PropositionState, PropositionType, QueryMsg},
+            oracle::OracleType,
+            state::LoanToValue,
+        };
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            set_oracle_symbols(&mut app, &cw_template_contract, &[INJ_DENOM, ATOM_DENOM]);
+            (app, cw_template_contract)
+        }
+
+        fn set_price(app: &mut App, price: Decimal) {
+            app.init_modules(|router, _, storage| {
+                router
+                    .custom
+                    .set_price(storage, INJ_DENOM, ATOM_DENOM, price)
+            });
+        }
+
+        fn loan_to_value(max_ltv: &str, liquidation_ltv: &str) -> LoanToValue {
+            LoanToValue {
+                max_ltv: max_ltv.parse().unwrap(),
+                liquidation_ltv: liquidation_ltv.parse().unwrap(),
+                liquidation_bonus: Decimal::percent(5),
+                oracle_type: OracleType::PriceFeed,
+            }
+        }
+
+        fn create_ask(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            ltv: LoanToValue,
+        ) -> bool {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(200, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: Some(ltv),
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(210, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).is_ok()
+        }
+
+        fn accept(app: &mut App, cw_template_contract: &CwTemplateContract) -> bool {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).is_ok()
+        }
+
+        fn liquidate(app: &mut App, cw_template_contract: &CwTemplateContract) -> bool {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::Liquidate { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).is_ok()
+        }
+
+        #[test]
+        fn test_ltv_needs_oracle_symbols() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            set_oracle_symbols(&mut app, &cw_template_contract, &[INJ_DENOM]);
+
+            assert!(!create_ask(
+                &mut app,
+                &cw_template_contract,
+                loan_to_value("0.6", "0.8")
+            ));
+        }
+
+        #[test]
+        fn test_invalid_ltv() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            assert!(!create_ask(
+                &mut app,
+                &cw_template_contract,
+                loan_to_value("0.9", "0.8")
+            ));
+        }
+
+        #[test]
+        fn test_accept_above_max_ltv() {
+            let (mut app, cw_template_contract) = init_test_case();
+            set_price(&mut app, Decimal::percent(50));
+
+            assert!(create_ask(
+                &mut app,
+                &cw_template_contract,
+                loan_to_value("0.6", "0.8")
+            ));
+            // 100 ATOM against 200 INJ worth 100 ATOM
+            assert!(!accept(&mut app, &cw_template_contract));
+
+            set_price(&mut app, Decimal::one());
+            assert!(accept(&mut app, &cw_template_contract));
+        }
+
+        #[test]
+        fn test_liquidate() {
+            let (mut app, cw_template_contract) = init_test_case();
+            set_price(&mut app, Decimal::one());
+
+            assert!(create_ask(
+                &mut app,
+                &cw_template_contract,
+                loan_to_value("0.6", "0.8")
+            ));
+            assert!(accept(&mut app, &cw_template_contract));
+            assert!(!liquidate(&mut app, &cw_template_contract));
+
+            // 100 ATOM against 200 INJ worth 120 ATOM
+            set_price(&mut app, Decimal::percent(60));
+            assert!(liquidate(&mut app, &cw_template_contract));
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.state, PropositionState::Liquidated);
+
+            assert_eq!(
+                app.wrap().query_balance(ADMIN, INJ_DENOM).unwrap().amount,
+                Uint128::new(1010)
+            );
+            assert_eq!(
+                app.wrap().query_balance(BOB, INJ_DENOM).unwrap().amount,
+                Uint128::new(1200)
+            );
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+        }
+    }
+
+    mod fee_test {
+
+        use cosmwasm_std::Timestamp;
+
+        use super::*;
+        use crate::{
+            msg::{ExecuteMsg, FundsMode, PropositionType, QueryMsg},
+            state::{Config, TermLimits},
+        };
+
+        const TREASURY: &str = "inj1treasury";
+
+        fn init_test_case(fee_on_default: bool) -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = instantiate_with(InstantiateMsg {
+                owner: None,
+                treasury: TREASURY.to_string(),
+                fee_bps: 500,
+                fee_on_default,
+            });
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(200, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(20, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(220, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn balance(app: &App, address: &str) -> Uint128 {
+            app.wrap().query_balance(address, INJ_DENOM).unwrap().amount
+        }
+
+        #[test]
+        fn test_fee_on_premium() {
+            let (app, _) = init_test_case(false);
+
+            assert_eq!(balance(&app, TREASURY), Uint128::new(1));
+            assert_eq!(balance(&app, BOB), Uint128::new(1019));
+        }
+
+        #[test]
+        fn test_fee_on_default() {
+            let (mut app, cw_template_contract) = init_test_case(true);
+
+            app.update_block(|f| f.time = Timestamp::from_seconds(9999999999));
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, TREASURY), Uint128::new(11));
+            assert_eq!(balance(&app, BOB), Uint128::new(1209));
+        }
+
+        #[test]
+        fn test_update_config() {
+            let (mut app, cw_template_contract) = init_test_case(false);
+
+            let msg = ExecuteMsg::UpdateConfig {
+                owner: None,
+                treasury: None,
+                fee_bps: Some(10_001),
+                fee_on_default: None,
+                note_contract: None,
+                grace_period: None,
+                late_fee_bps: None,
+                funds_mode: None,
+                term_limits: None,
+                collateral_denoms: None,
+                min_collateral_ratio: None,
+                token_contracts: None,
+                oracle_symbols: None,
+            };
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            assert!(app.execute(Addr::unchecked(ADMIN), cosmos_msg).is_err());
+
+            let msg = ExecuteMsg::UpdateConfig {
+                owner: None,
+                treasury: None,
+                fee_bps: Some(100),
+                fee_on_default: Some(true),
+                note_contract: None,
+                grace_period: None,
+                late_fee_bps: None,
+                funds_mode: None,
+                term_limits: None,
+                collateral_denoms: None,
+                min_collateral_ratio: None,
+                token_contracts: None,
+                oracle_symbols: None,
+            };
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let config: Config = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::Config {})
+                .unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    owner: Addr::unchecked(ADMIN),
+                    treasury: Addr::unchecked(TREASURY),
+                    fee_bps: 100,
+                    fee_on_default: true,
+                    note_contract: None,
+                    grace_period: 0,
+                    late_fee_bps: 0,
+                    funds_mode: FundsMode::Exact,
+                    term_limits: TermLimits::default(),
+                    collateral_denoms: vec![],
+                    min_collateral_ratio: Decimal::zero(),
+                    token_contracts: vec![],
+                    oracle_symbols: vec![],
+                }
+            );
+        }
+    }
+
+    mod pause_test {
+
+        use super::*;
+        use crate::{
+            msg::{ExecuteMsg, Operation, PropositionType, QueryMsg, SudoMsg},
+            state::PauseFlags,
+        };
+
+        fn create_msg(app: &App) -> ExecuteMsg {
+            ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            }
+        }
+
+        fn create(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+        ) -> AnyResult<AppResponse> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(create_msg(app), vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg)
+        }
+
+        fn set_paused(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            operation: Operation,
+            paused: bool,
+        ) -> AnyResult<AppResponse> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::SetPaused { operation, paused })
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg)
+        }
+
+        #[test]
+        fn test_pause_create_keeps_reject_open() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract).unwrap();
+
+            assert!(set_paused(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                Operation::Create,
+                true
+            )
+            .is_err());
+            set_paused(
+                &mut app,
+                &cw_template_contract,
+                ADMIN,
+                Operation::Create,
+                true,
+            )
+            .unwrap();
+
+            let err = create(&mut app, &cw_template_contract).unwrap_err();
+            assert_eq!(err.root_cause().to_string(), "Paused");
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::RejectProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            set_paused(
+                &mut app,
+                &cw_template_contract,
+                ADMIN,
+                Operation::Create,
+                false,
+            )
+            .unwrap();
+            create(&mut app, &cw_template_contract).unwrap();
+        }
+
+        #[test]
+        fn test_sudo_pause_accept() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract).unwrap();
+
+            app.wasm_sudo(
+                cw_template_contract.addr(),
+                &SudoMsg::SetPaused {
+                    operation: Operation::Accept,
+                    paused: true,
+                },
+            )
+            .unwrap();
+
+            let flags: PauseFlags = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::PauseFlags {})
+                .unwrap();
+            assert_eq!(
+                flags,
+                PauseFlags {
+                    accept: true,
+                    ..PauseFlags::default()
+                }
+            );
+
+            let accept = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(BOB), accept.clone()).is_err());
+
+            set_paused(
+                &mut app,
+                &cw_template_contract,
+                ADMIN,
+                Operation::Accept,
+                false,
+            )
+            .unwrap();
+            app.execute(Addr::unchecked(BOB), accept).unwrap();
+        }
+    }
+
+    mod migration_test {
+
+        use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint64};
+        use cw2::{set_contract_version, ContractVersion};
+
+        use super::*;
+        use crate::{
+            migrations::{PropositionV1, PROPOSITIONS_V1},
+            msg::{
+                CountResponse, ExecuteMsg, MigrateMsg, Operation, PropositionResponse,
+                PropositionState, PropositionType, PropositionsResponse, QueryMsg,
+            },
+            state::{Config, PROPOSITION_COUNT},
+            ContractError,
+        };
+
+        /// Stand-in for a 1.x deployment, instantiating stores its propositions
+        /// under the given contract version
+        fn legacy_instantiate(
+            deps: DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            msg: ContractVersion,
+        ) -> StdResult<Response> {
+            set_contract_version(deps.storage, msg.contract, msg.version)?;
+
+            let legacy = PropositionV1 {
+                owner: Addr::unchecked(ALICE),
+                proposition_type: PropositionType::Ask,
+                state: PropositionState::Active,
+                deposit: Coin::new(100, INJ_DENOM),
+                assets: Coin::new(100, ATOM_DENOM),
+                premium: Coin::new(10, INJ_DENOM),
+                period: 100,
+                expiry: 1000,
+                contractor: None,
+            };
+            PROPOSITIONS_V1.save(deps.storage, 1, &legacy)?;
+            PROPOSITIONS_V1.save(
+                deps.storage,
+                2,
+                &PropositionV1 {
+                    state: PropositionState::Accepted,
+                    contractor: Some(Addr::unchecked(BOB)),
+                    ..legacy
+                },
+            )?;
+            PROPOSITION_COUNT.save(deps.storage, &2)?;
+
+            Ok(Response::new())
+        }
+
+        fn legacy_execute(
+            _deps: DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            _msg: Empty,
+        ) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+
+        fn legacy_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+            Ok(Binary::default())
+        }
+
+        fn legacy_template() -> Box<dyn Contract<Empty, InjectiveQueryWrapper>> {
+            let contract =
+                ContractWrapper::new_with_empty(legacy_execute, legacy_instantiate, legacy_query);
+            Box::new(contract)
+        }
+
+        fn init_legacy(contract: &str, version: &str) -> (App, Addr, u64) {
+            let mut app = mock_app();
+            let legacy_id = app.store_code(legacy_template());
+            let code_id = app.store_code(contract_template());
+
+            let addr = app
+                .instantiate_contract(
+                    legacy_id,
+                    Addr::unchecked(ADMIN),
+                    &ContractVersion {
+                        contract: contract.to_string(),
+                        version: version.to_string(),
+                    },
+                    &[],
+                    "legacy",
+                    Some(ADMIN.to_string()),
+                )
+                .unwrap();
+
+            (app, addr, code_id)
+        }
+
+        fn migrate_msg() -> MigrateMsg {
+            MigrateMsg {
+                owner: Some(ADMIN.to_string()),
+                treasury: Some(ADMIN.to_string()),
+                limit: None,
+            }
+        }
+
+        #[test]
+        fn test_migrate_from_v1() {
+            let (mut app, addr, code_id) = init_legacy("crates.io:qwerty:crypto-pawn", "1.0.0");
+
+            app.migrate_contract(
+                Addr::unchecked(ADMIN),
+                addr.clone(),
+                &migrate_msg(),
+                code_id,
+            )
+            .unwrap();
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    &addr,
+                    &QueryMsg::GetProposition { proposition_id: 2 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.assets, Coin::new(100, ATOM_DENOM).into());
+            assert_eq!(proposition.repaid, Uint128::zero());
+
+            // Indexes did not exist in 1.x and are built by the migration
+            let by_contractor: PropositionsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &addr,
+                    &QueryMsg::GetPropositionsByContractor {
+                        contractor: BOB.to_string(),
+                        start_before: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(by_contractor.propositions.len(), 1);
+            assert_eq!(by_contractor.propositions[0].id, 2);
+
+            let config: Config = app
+                .wrap()
+                .query_wasm_smart(&addr, &QueryMsg::Config {})
+                .unwrap();
+            assert_eq!(config.treasury, Addr::unchecked(ADMIN));
+            assert_eq!(config.fee_bps, 0);
+
+            let version = cw2::CONTRACT.query(&app.wrap(), addr.clone()).unwrap();
+            assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+            // Migrating again to the same version keeps the data
+            app.migrate_contract(
+                Addr::unchecked(ADMIN),
+                addr.clone(),
+                &migrate_msg(),
+                code_id,
+            )
+            .unwrap();
+            let response: CountResponse = app
+                .wrap()
+                .query_wasm_smart(&addr, &QueryMsg::GetPropositionCount {})
+                .unwrap();
+            assert_eq!(response.count, Uint64::new(2));
+        }
+
+        #[test]
+        fn test_migrate_from_v1_in_batches() {
+            let (mut app, addr, code_id) = init_legacy("crates.io:qwerty:crypto-pawn", "1.0.0");
+
+            let msg = MigrateMsg {
+                limit: Some(1),
+                ..migrate_msg()
+            };
+            app.migrate_contract(Addr::unchecked(ADMIN), addr.clone(), &msg, code_id)
+                .unwrap();
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    &addr,
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.assets, Coin::new(100, ATOM_DENOM).into());
+
+            // Half migrated storage takes nothing but the rest of the migration
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ALICE),
+                    addr.clone(),
+                    &ExecuteMsg::RejectProposition { proposition_id: 1 },
+                    &[],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::MigrationInProgress {}
+            ));
+
+            app.execute_contract(
+                Addr::unchecked(BOB),
+                addr.clone(),
+                &ExecuteMsg::MigratePropositions { limit: None },
+                &[],
+            )
+            .unwrap();
+            assert!(app
+                .execute_contract(
+                    Addr::unchecked(BOB),
+                    addr.clone(),
+                    &ExecuteMsg::MigratePropositions { limit: None },
+                    &[],
+                )
+                .is_err());
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    &addr,
+                    &QueryMsg::GetProposition { proposition_id: 2 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.contractor, Some(Addr::unchecked(BOB)));
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                addr,
+                &ExecuteMsg::SetPaused {
+                    operation: Operation::Create,
+                    paused: true,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn test_migrate_from_v1_requires_config() {
+            let (mut app, addr, code_id) = init_legacy("crates.io:qwerty:crypto-pawn", "1.0.0");
+
+            let msg = MigrateMsg {
+                owner: None,
+                treasury: None,
+                limit: None,
+            };
+            assert!(app
+                .migrate_contract(Addr::unchecked(ADMIN), addr, &msg, code_id)
+                .is_err());
+        }
+
+        #[test]
+        fn test_refuse_downgrade_and_foreign_contract() {
+            let (mut app, addr, code_id) = init_legacy("crates.io:qwerty:crypto-pawn", "99.0.0");
+            let err = app
+                .migrate_contract(Addr::unchecked(ADMIN), addr, &migrate_msg(), code_id)
+                .unwrap_err();
+            assert_eq!(err.root_cause().to_string(), "CannotMigrate");
+
+            let (mut app, addr, code_id) = init_legacy("crates.io:cw20-base", "0.1.0");
+            let err = app
+                .migrate_contract(Addr::unchecked(ADMIN), addr, &migrate_msg(), code_id)
+                .unwrap_err();
+            assert_eq!(err.root_cause().to_string(), "CannotMigrate");
+        }
+    }
+
+    mod counter_offer_test {
+
+        use super::*;
+        use crate::{
+            msg::{
+                ExecuteMsg, PropositionResponse, PropositionState, PropositionType, QueryMsg,
+                SudoMsg,
+            },
+            state::CounterOffer,
+            ContractError,
+        };
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn counter_offer(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            assets: u128,
+            premium: u128,
+        ) {
+            try_counter_offer(app, cw_template_contract, sender, assets, premium).unwrap();
+        }
+
+        fn try_counter_offer(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            assets: u128,
+            premium: u128,
+        ) -> AnyResult<AppResponse> {
+            let msg = ExecuteMsg::MakeCounterOffer {
+                proposition_id: 1,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(assets, ATOM_DENOM).into(),
+                premium: Coin::new(premium, INJ_DENOM).into(),
+                period: 200,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(assets, ATOM_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg)
+        }
+
+        fn offers(
+            app: &App,
+            cw_template_contract: &CwTemplateContract,
+        ) -> Vec<(u64, CounterOffer)> {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetCounterOffers {
+                        proposition_id: 1,
+                        start_before: None,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+        }
+
+        #[test]
+        fn test_accept_counter_offer() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            counter_offer(&mut app, &cw_template_contract, BOB, 80, 15);
+            counter_offer(&mut app, &cw_template_contract, ADMIN, 90, 10);
+            assert_eq!(offers(&app, &cw_template_contract).len(), 2);
+
+            let by_owner: Vec<(u64, CounterOffer)> = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetCounterOffersByOwner {
+                        owner: BOB.to_string(),
+                        start_before: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(by_owner.len(), 1);
+            assert_eq!(by_owner[0].0, 1);
+
+            // Bob asks for 5 more premium than Alice escrowed
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::AcceptCounterOffer {
+                    counter_offer_id: 1,
+                })
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptCounterOffer {
+                        counter_offer_id: 1,
+                    },
+                    vec![Coin::new(5, INJ_DENOM)],
+                )
+                .unwrap();
+            assert!(app
+                .execute(Addr::unchecked(BOB), cosmos_msg.clone())
+                .is_err());
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.state, PropositionState::Accepted);
+            assert_eq!(proposition.contractor, Some(Addr::unchecked(BOB)));
+            assert_eq!(proposition.period, 200);
+
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(1080));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(885));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(920));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1015));
+            // The other offer is refunded
+            assert_eq!(balance(&app, ADMIN, ATOM_DENOM), Uint128::new(1000));
+            assert!(offers(&app, &cw_template_contract).is_empty());
+        }
+
+        #[test]
+        fn test_withdraw_counter_offer() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            counter_offer(&mut app, &cw_template_contract, BOB, 80, 15);
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(920));
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::WithdrawCounterOffer {
+                    counter_offer_id: 1,
+                })
+                .unwrap();
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert!(offers(&app, &cw_template_contract).is_empty());
+        }
+
+        #[test]
+        fn test_reject_refunds_counter_offers() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            counter_offer(&mut app, &cw_template_contract, BOB, 80, 15);
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::RejectProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+            assert!(offers(&app, &cw_template_contract).is_empty());
+        }
+
+        #[test]
+        fn test_counter_offer_limits() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            // Dust cannot take up the slots
+            let err = try_counter_offer(&mut app, &cw_template_contract, BOB, 10, 0).unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::InvalidCounterOffer {}
+            ));
+
+            counter_offer(&mut app, &cw_template_contract, BOB, 50, 0);
+            let err = try_counter_offer(&mut app, &cw_template_contract, BOB, 80, 0).unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::TooManyCounterOffers {}
+            ));
+            assert_eq!(offers(&app, &cw_template_contract).len(), 1);
+        }
+
+        #[test]
+        fn test_expiry_refunds_counter_offers() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            counter_offer(&mut app, &cw_template_contract, BOB, 80, 15);
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(920));
+
+            app.update_block(|block| block.time = block.time.plus_seconds(1000));
+            app.wasm_sudo(cw_template_contract.addr(), &SudoMsg::BeginBlocker {})
+                .unwrap();
+
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+            assert!(offers(&app, &cw_template_contract).is_empty());
+        }
+
+        #[test]
+        fn test_no_premium_on_interest_priced_loans() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(0, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: Some(Decimal::percent(10)),
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(100, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let err = try_counter_offer(&mut app, &cw_template_contract, BOB, 80, 5).unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::InvalidInterestRate {}
+            ));
+            counter_offer(&mut app, &cw_template_contract, BOB, 80, 0);
+        }
+    }
+
+    mod partial_fill_test {
+
+        use super::*;
+        use crate::{
+            msg::{ExecuteMsg, PropositionState, PropositionType, QueryMsg},
+            state::Fill,
+        };
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Bid,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: Some(Uint128::new(50)),
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(100, ATOM_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn fill(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            amount: u128,
+            funds: u128,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::FillProposition {
+                        proposition_id: 1,
+                        amount: Uint128::new(amount),
+                    },
+                    vec![Coin::new(funds, INJ_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        fn fills(app: &App, cw_template_contract: &CwTemplateContract) -> Vec<Fill> {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetFills { proposition_id: 1 },
+                )
+                .unwrap()
+        }
+
+        #[test]
+        fn test_fully_filled() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            // Not syndicated, a single contractor takes it all
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(110, INJ_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(BOB), cosmos_msg).is_err());
+
+            // 60% of the deposit and premium
+            fill(&mut app, &cw_template_contract, BOB, 60, 66).unwrap();
+            assert_eq!(
+                proposition(&app, &cw_template_contract).state,
+                PropositionState::Active
+            );
+            assert!(fill(&mut app, &cw_template_contract, ADMIN, 50, 55).is_err());
+
+            fill(&mut app, &cw_template_contract, ADMIN, 40, 44).unwrap();
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.state, PropositionState::Accepted);
+            assert_eq!(loan.filled, Uint128::new(100));
+            assert_eq!(fills(&app, &cw_template_contract).len(), 2);
+
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(900));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1010));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1060));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(934));
+            assert_eq!(balance(&app, ADMIN, ATOM_DENOM), Uint128::new(1040));
+            assert_eq!(balance(&app, ADMIN, INJ_DENOM), Uint128::new(956));
+
+            // Bob pays his share back and gets his deposit share
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(60, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(960));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(994));
+            assert_eq!(
+                proposition(&app, &cw_template_contract).state,
+                PropositionState::Accepted
+            );
+
+            // Admin defaults, Alice claims what is left
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1050));
+            assert_eq!(
+                proposition(&app, &cw_template_contract).state,
+                PropositionState::Closed
+            );
+            assert!(fills(&app, &cw_template_contract).is_empty());
+        }
+
+        #[test]
+        fn test_activate_partial_fill() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            fill(&mut app, &cw_template_contract, BOB, 30, 33).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::ActivateProposition { proposition_id: 1 })
+                .unwrap();
+            // Below the minimum
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+
+            fill(&mut app, &cw_template_contract, BOB, 30, 33).unwrap();
+            assert!(app
+                .execute(Addr::unchecked(BOB), cosmos_msg.clone())
+                .is_err());
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.state, PropositionState::Accepted);
+            assert_eq!(loan.assets.amount, Uint128::new(60));
+            assert_eq!(loan.deposit.amount, Uint128::new(60));
+            assert_eq!(loan.premium.amount, Uint128::new(6));
+            assert_eq!(
+                fills(&app, &cw_template_contract),
+                vec![Fill {
+                    lender: Addr::unchecked(BOB),
+                    amount: Uint128::new(60),
+                    deposit: Uint128::new(60),
+                    premium: Uint128::new(6),
+                }]
+            );
+
+            // The unfilled part goes back to Alice with the premium
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(940));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1006));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1060));
+        }
+
+        #[test]
+        fn test_reject_refunds_fills() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            fill(&mut app, &cw_template_contract, BOB, 30, 33).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::RejectProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1000));
+            assert!(fills(&app, &cw_template_contract).is_empty());
+        }
+    }
+
+    mod vault_test {
+
+        use super::*;
+        use crate::{
+            asset::AssetInfo,
+            msg::{ExecuteMsg, PropositionResponse, PropositionState, QueryMsg, SudoMsg},
+            state::{AmountLimit, TermLimits, Vault, VaultTerms},
+            ContractError,
+        };
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateVault {
+                assets: AssetInfo::Native(ATOM_DENOM.to_string()),
+                terms: VaultTerms {
+                    deposit: AssetInfo::Native(INJ_DENOM.to_string()),
+                    collateral_ratio: Decimal::percent(200),
+                    premium_rate: Decimal::percent(10),
+                    period: 100,
+                },
+            };
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            deposit(&mut app, &cw_template_contract, ALICE, 100);
+
+            // Bob pawns 100 INJ for 50 ATOM, paying 5 ATOM of premium
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::BorrowFromVault {
+                        vault_id: 1,
+                        amount: Uint128::new(50),
+                    },
+                    vec![Coin::new(100, INJ_DENOM), Coin::new(5, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn deposit(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            amount: u128,
+        ) {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::DepositToVault {
+                        vault_id: 1,
+                        amount: Uint128::new(amount),
+                    },
+                    vec![Coin::new(amount, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).unwrap();
+        }
+
+        fn withdraw(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            shares: u128,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::WithdrawFromVault {
+                    vault_id: 1,
+                    shares: Uint128::new(shares),
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        fn vault(app: &App, cw_template_contract: &CwTemplateContract) -> Vault {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetVault { vault_id: 1 },
+                )
+                .unwrap()
+        }
+
+        #[test]
+        fn test_borrow_and_repay() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            let loan = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(loan.state, PropositionState::Accepted);
+            assert_eq!(loan.vault, Some(1));
+            assert_eq!(loan.contractor, Some(cw_template_contract.addr()));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1045));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(900));
+
+            let pool = vault(&app, &cw_template_contract);
+            assert_eq!(pool.liquidity, Uint128::new(55));
+            assert_eq!(pool.lent, Uint128::new(50));
+
+            // The premium raised the share price to 1.05
+            deposit(&mut app, &cw_template_contract, ADMIN, 21);
+            let shares: Uint128 = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetVaultShares {
+                        vault_id: 1,
+                        owner: ADMIN.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(shares, Uint128::new(20));
+
+            // Half the pool is lent out
+            assert!(withdraw(&mut app, &cw_template_contract, ALICE, 100).is_err());
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(50, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1000));
+            assert_eq!(
+                vault(&app, &cw_template_contract).liquidity,
+                Uint128::new(126)
+            );
+
+            withdraw(&mut app, &cw_template_contract, ALICE, 100).unwrap();
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(1005));
+            withdraw(&mut app, &cw_template_contract, ADMIN, 20).unwrap();
+            assert_eq!(balance(&app, ADMIN, ATOM_DENOM), Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_default_written_off() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+
+            // Anyone settles the default, the deposit stays with the vault
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+            let pool = vault(&app, &cw_template_contract);
+            assert_eq!(pool.lent, Uint128::zero());
+            assert_eq!(pool.liquidity, Uint128::new(55));
+            assert_eq!(pool.collateral, vec![Coin::new(100, INJ_DENOM).into()]);
+
+            // Later deposits are taken, but do not share in the deposit
+            deposit(&mut app, &cw_template_contract, BOB, 10);
+
+            // Withdrawing takes the deposit of every held share
+            withdraw(&mut app, &cw_template_contract, ALICE, 50).unwrap();
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(927));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1100));
+            withdraw(&mut app, &cw_template_contract, ALICE, 50).unwrap();
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(954));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1100));
+            assert!(vault(&app, &cw_template_contract).collateral.is_empty());
+
+            withdraw(&mut app, &cw_template_contract, BOB, 18).unwrap();
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1046));
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(900));
+        }
+
+        #[test]
+        fn test_default_written_off_by_begin_blocker() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            app.wasm_sudo(cw_template_contract.addr(), &SudoMsg::BeginBlocker {})
+                .unwrap();
+
+            let loan = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(loan.state, PropositionState::Closed);
+            let pool = vault(&app, &cw_template_contract);
+            assert_eq!(pool.lent, Uint128::zero());
+            assert_eq!(pool.collateral, vec![Coin::new(100, INJ_DENOM).into()]);
+
+            // Withdrawn at the share price after the loss, before anyone closes
+            withdraw(&mut app, &cw_template_contract, ALICE, 50).unwrap();
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(927));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1100));
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ADMIN), cosmos_msg).is_err());
+        }
+
+        #[test]
+        fn test_borrow_within_term_limits() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: Some(TermLimits {
+                        min_period: 0,
+                        max_period: None,
+                        amounts: vec![AmountLimit {
+                            info: AssetInfo::Native(ATOM_DENOM.to_string()),
+                            min: Uint128::new(10),
+                            max: Some(Uint128::new(20)),
+                        }],
+                    }),
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let borrow = |app: &mut App, amount: u128| {
+                let cosmos_msg = cw_template_contract
+                    .call_with_funds(
+                        ExecuteMsg::BorrowFromVault {
+                            vault_id: 1,
+                            amount: Uint128::new(amount),
+                        },
+                        vec![
+                            Coin::new(amount * 2, INJ_DENOM),
+                            Coin::new(amount / 10, ATOM_DENOM),
+                        ],
+                    )
+                    .unwrap();
+                app.execute(Addr::unchecked(BOB), cosmos_msg)
+                    .err()
+                    .map(|err| err.downcast().unwrap())
+            };
+            assert!(matches!(
+                borrow(&mut app, 30),
+                Some(ContractError::InvalidAmount {})
+            ));
+            assert!(borrow(&mut app, 20).is_none());
+        }
+    }
+
+    mod transfer_position_test {
+
+        use super::*;
+        use crate::msg::{
+            ExecuteMsg, PositionSide, PropositionResponse, PropositionType, QueryMsg,
+        };
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn transfer(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            side: PositionSide,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::TransferPosition {
+                    proposition_id: 1,
+                    side,
+                    recipient: ADMIN.to_string(),
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        #[test]
+        fn test_transfer_contractor_side() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            assert!(transfer(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                PositionSide::Contractor
+            )
+            .is_err());
+            transfer(
+                &mut app,
+                &cw_template_contract,
+                BOB,
+                PositionSide::Contractor,
+            )
+            .unwrap();
+            assert!(transfer(
+                &mut app,
+                &cw_template_contract,
+                BOB,
+                PositionSide::Contractor
+            )
+            .is_err());
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.contractor, Some(Addr::unchecked(ADMIN)));
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            // The repayment goes to the new holder
+            assert_eq!(balance(&app, ADMIN, ATOM_DENOM), Uint128::new(1100));
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(900));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+        }
+
+        #[test]
+        fn test_transfer_owner_side_then_default() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            transfer(&mut app, &cw_template_contract, ALICE, PositionSide::Owner).unwrap();
+
+            // Alice no longer holds the loan she took
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+
+            // Admin now holds the other side
+            assert!(transfer(
+                &mut app,
+                &cw_template_contract,
+                BOB,
+                PositionSide::Contractor
+            )
+            .is_err());
+
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::CloseProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1110));
+        }
+    }
+
+    mod note_test {
+
+        use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse};
+
+        use super::*;
+        use crate::msg::{
+            ExecuteMsg, PositionSide, PropositionType, PropositionsResponse, QueryMsg, SudoMsg,
+        };
+
+        fn cw721_template() -> Box<dyn Contract<Empty, InjectiveQueryWrapper>> {
+            let contract = ContractWrapper::new_with_empty(
+                cw721_base::entry::execute,
+                cw721_base::entry::instantiate,
+                cw721_base::entry::query,
+            );
+            Box::new(contract)
+        }
+
+        fn init_test_case() -> (App, CwTemplateContract, Addr) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cw721_id = app.store_code(cw721_template());
+            let notes = app
+                .instantiate_contract(
+                    cw721_id,
+                    Addr::unchecked(ADMIN),
+                    &cw721_base::InstantiateMsg {
+                        name: "Pawn Notes".to_string(),
+                        symbol: "NOTE".to_string(),
+                        minter: cw_template_contract.addr().to_string(),
+                    },
+                    &[],
+                    "pawn notes",
+                    None,
+                )
+                .unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: Some(notes.to_string()),
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: None,
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract, notes)
+        }
+
+        fn note_owner(app: &App, notes: &Addr, token_id: &str) -> String {
+            app.wrap()
+                .query_wasm_smart::<OwnerOfResponse>(
+                    notes,
+                    &Cw721QueryMsg::OwnerOf {
+                        token_id: token_id.to_string(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap()
+                .owner
+        }
+
+        #[test]
+        fn test_repayment_follows_note() {
+            let (mut app, cw_template_contract, notes) = init_test_case();
+
+            // Bob provided the assets and holds the right to repayment
+            assert_eq!(note_owner(&app, &notes, "1-contractor"), BOB);
+
+            app.execute_contract(
+                Addr::unchecked(BOB),
+                notes.clone(),
+                &Cw721ExecuteMsg::TransferNft {
+                    recipient: ADMIN.to_string(),
+                    token_id: "1-contractor".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                proposition(&app, &cw_template_contract).contractor,
+                Some(Addr::unchecked(ADMIN))
+            );
+
+            // The side moves with its note only
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::TransferPosition {
+                    proposition_id: 1,
+                    side: PositionSide::Contractor,
+                    recipient: BOB.to_string(),
+                })
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ADMIN), cosmos_msg).is_err());
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let balance = |address: &str| {
+                app.wrap()
+                    .query_balance(address, ATOM_DENOM)
+                    .unwrap()
+                    .amount
+            };
+            assert_eq!(balance(ADMIN), Uint128::new(1100));
+            assert_eq!(balance(BOB), Uint128::new(900));
+        }
+
+        #[test]
+        fn test_mint_owner_note() {
+            let (mut app, cw_template_contract, notes) = init_test_case();
+
+            let mint = |side| {
+                cw_template_contract
+                    .call(ExecuteMsg::MintNote {
+                        proposition_id: 1,
+                        side,
+                    })
+                    .unwrap()
+            };
+
+            assert!(app
+                .execute(Addr::unchecked(BOB), mint(PositionSide::Owner))
+                .is_err());
+            assert!(app
+                .execute(Addr::unchecked(BOB), mint(PositionSide::Contractor))
+                .is_err());
+            app.execute(Addr::unchecked(ALICE), mint(PositionSide::Owner))
+                .unwrap();
+            assert_eq!(note_owner(&app, &notes, "1-owner"), ALICE);
+
+            app.execute_contract(
+                Addr::unchecked(ALICE),
+                notes.clone(),
+                &Cw721ExecuteMsg::TransferNft {
+                    recipient: ADMIN.to_string(),
+                    token_id: "1-owner".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            // Admin now owes the repayment and gets the deposit back
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            assert!(app
+                .execute(Addr::unchecked(ALICE), cosmos_msg.clone())
+                .is_err());
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let inj = app.wrap().query_balance(ADMIN, INJ_DENOM).unwrap().amount;
+            assert_eq!(inj, Uint128::new(1100));
+        }
+
+        #[test]
+        fn test_burned_note_keeps_holder() {
+            let (mut app, cw_template_contract, notes) = init_test_case();
+
+            app.execute_contract(
+                Addr::unchecked(BOB),
+                notes,
+                &Cw721ExecuteMsg::Burn {
+                    token_id: "1-contractor".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            // The loan is still repaid, to the holder last stored
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let atom = app.wrap().query_balance(BOB, ATOM_DENOM).unwrap().amount;
+            assert_eq!(atom, Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_default_stores_note_holder() {
+            let (mut app, cw_template_contract, notes) = init_test_case();
+
+            app.execute_contract(
+                Addr::unchecked(BOB),
+                notes,
+                &Cw721ExecuteMsg::TransferNft {
+                    recipient: ADMIN.to_string(),
+                    token_id: "1-contractor".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            app.wasm_sudo(cw_template_contract.addr(), &SudoMsg::BeginBlocker {})
+                .unwrap();
+
+            let by_contractor: PropositionsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPropositionsByContractor {
+                        contractor: ADMIN.to_string(),
+                        start_before: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(by_contractor.propositions.len(), 1);
+        }
+    }
+
+    mod interest_test {
+
+        use super::*;
+        use crate::{
+            msg::{
+                ExecuteMsg, Payoff, PropositionResponse, PropositionState, PropositionType,
+                QueryMsg,
+            },
+            state::SECONDS_PER_YEAR,
+        };
+
+        fn create_msg(app: &App, premium: u128) -> ExecuteMsg {
+            ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(premium, INJ_DENOM).into(),
+                period: SECONDS_PER_YEAR,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: Some(Decimal::percent(50)),
+            }
+        }
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(create_msg(&app, 0), vec![Coin::new(100, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn payoff(app: &App, cw_template_contract: &CwTemplateContract) -> Payoff {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPayoff { proposition_id: 1 },
+                )
+                .unwrap()
+        }
+
+        #[test]
+        fn test_premium_and_rate_exclusive() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(create_msg(&app, 10), vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+        }
+
+        #[test]
+        fn test_payoff_only_for_loans() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(create_msg(&app, 0), vec![Coin::new(100, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert!(app
+                .wrap()
+                .query_wasm_smart::<Payoff>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPayoff { proposition_id: 1 },
+                )
+                .is_err());
+        }
+
+        #[test]
+        fn test_interest_accrues_on_outstanding() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            assert_eq!(
+                payoff(&app, &cw_template_contract).interest.amount,
+                Uint128::zero()
+            );
+
+            // Half a year at 50% on 100
+            app.update_block(|block| block.time = block.time.plus_seconds(SECONDS_PER_YEAR / 2));
+            let due = payoff(&app, &cw_template_contract);
+            assert_eq!(due.principal.amount, Uint128::new(100));
+            assert_eq!(due.interest.amount, Uint128::new(25));
+
+            // The interest due comes with any repayment
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::RepayLoan {
+                        proposition_id: 1,
+                        amount: Uint128::new(40),
+                    },
+                    vec![Coin::new(40, ATOM_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), cosmos_msg).is_err());
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::RepayLoan {
+                        proposition_id: 1,
+                        amount: Uint128::new(40),
+                    },
+                    vec![Coin::new(65, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(965));
+
+            // A quarter at 50% on the 60 left
+            app.update_block(|block| block.time = block.time.plus_seconds(SECONDS_PER_YEAR / 4));
+            assert_eq!(
+                payoff(&app, &cw_template_contract).interest.amount,
+                Uint128::new(7)
+            );
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(67, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let loan = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(loan.state, PropositionState::Closed);
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1032));
+            assert_eq!(balance(&app, ALICE, ATOM_DENOM), Uint128::new(968));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+        }
+    }
+
+    mod grace_period_test {
+
+        use super::*;
+        use crate::asset::Asset;
+        use crate::msg::{
+            ExecuteMsg, LoanPhase, Payoff, PropositionResponse, PropositionState, PropositionType,
+            QueryMsg, SudoMsg,
+        };
+        use crate::ContractError;
+
+        fn init_test_case() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: Some(50),
+                    late_fee_bps: Some(500),
+                    funds_mode: None,
+                    term_limits: None,
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn phase(app: &App, cw_template_contract: &CwTemplateContract) -> LoanPhase {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetLoanPhase { proposition_id: 1 },
+                )
+                .unwrap()
+        }
+
+        fn close(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            funds: Vec<Coin>,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(ExecuteMsg::CloseProposition { proposition_id: 1 }, funds)
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        #[test]
+        fn test_repay_late_in_grace_period() {
+            let (mut app, cw_template_contract) = init_test_case();
+            assert_eq!(phase(&app, &cw_template_contract), LoanPhase::Active);
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            assert_eq!(phase(&app, &cw_template_contract), LoanPhase::Grace);
+
+            // Not forfeit yet, and still accepted after the begin blocker
+            assert!(close(&mut app, &cw_template_contract, BOB, vec![]).is_err());
+            app.wasm_sudo(cw_template_contract.addr(), &SudoMsg::BeginBlocker {})
+                .unwrap();
+            let loan = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(loan.state, PropositionState::Accepted);
+
+            // 5% late fee on top of the 100 owed
+            assert!(close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(100, ATOM_DENOM)]
+            )
+            .is_err());
+            close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(105, ATOM_DENOM)],
+            )
+            .unwrap();
+
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(1005));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+        }
+
+        #[test]
+        fn test_claim_after_grace_period() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(151));
+            assert_eq!(phase(&app, &cw_template_contract), LoanPhase::Defaulted);
+
+            assert!(close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(105, ATOM_DENOM)]
+            )
+            .is_err());
+            close(&mut app, &cw_template_contract, BOB, vec![]).unwrap();
+
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1110));
+        }
+
+        #[test]
+        fn test_payoff_in_grace_period() {
+            let (mut app, cw_template_contract) = init_test_case();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            let payoff: Payoff = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPayoff { proposition_id: 1 },
+                )
+                .unwrap();
+            assert_eq!(payoff.principal, Asset::native(100u128, ATOM_DENOM));
+            assert_eq!(payoff.late_fee, Asset::native(5u128, ATOM_DENOM));
+
+            // The quote is exactly what closing takes
+            let owed = payoff.principal.amount + payoff.interest.amount + payoff.late_fee.amount;
+            close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(owed.u128(), ATOM_DENOM)],
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn test_grace_period_bounded() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: Some(u64::MAX),
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: None,
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            let err = app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::InvalidPeriod {}
+            ));
+        }
+    }
+
+    mod event_test {
+
+        use cosmwasm_std::Event;
+        use cw_multi_test::AppResponse;
+
+        use super::*;
+        use crate::events::EVENT_VERSION;
+        use crate::msg::{ExecuteMsg, PropositionType};
+
+        fn create(app: &mut App, cw_template_contract: &CwTemplateContract) -> AppResponse {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap()
+        }
+
+        fn accept(app: &mut App, cw_template_contract: &CwTemplateContract) -> AppResponse {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap()
+        }
+
+        fn close(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            funds: Vec<Coin>,
+        ) -> AppResponse {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(ExecuteMsg::CloseProposition { proposition_id: 1 }, funds)
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).unwrap()
+        }
+
+        fn pawn_events(response: &AppResponse) -> Vec<&Event> {
+            response
+                .events
+                .iter()
+                .filter(|event| event.ty == "wasm-crypto_pawn")
+                .collect()
+        }
+
+        fn attribute<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.as_str())
+        }
+
+        #[test]
+        fn test_created_event_carries_terms() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let response = create(&mut app, &cw_template_contract);
+            let events = pawn_events(&response);
+            assert_eq!(events.len(), 1);
+
+            let event = events[0];
+            assert_eq!(attribute(event, "version"), Some(EVENT_VERSION));
+            assert_eq!(attribute(event, "action"), Some("created"));
+            assert_eq!(attribute(event, "proposition_id"), Some("1"));
+            assert_eq!(attribute(event, "proposition_type"), Some("Ask"));
+            assert_eq!(attribute(event, "state"), Some("Active"));
+            assert_eq!(attribute(event, "owner"), Some(ALICE));
+            assert_eq!(attribute(event, "contractor"), None);
+            assert_eq!(attribute(event, "deposit_denom"), Some(INJ_DENOM));
+            assert_eq!(attribute(event, "deposit_amount"), Some("100"));
+            assert_eq!(attribute(event, "assets_denom"), Some(ATOM_DENOM));
+            assert_eq!(attribute(event, "assets_amount"), Some("100"));
+            assert_eq!(attribute(event, "premium_amount"), Some("10"));
+            assert_eq!(attribute(event, "period"), Some("100"));
+        }
+
+        #[test]
+        fn test_accepted_and_closed_events() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract);
+
+            let response = accept(&mut app, &cw_template_contract);
+            let events = pawn_events(&response);
+            assert_eq!(events.len(), 1);
+            assert_eq!(attribute(events[0], "action"), Some("accepted"));
+            assert_eq!(attribute(events[0], "contractor"), Some(BOB));
+            let expiry = (app.block_info().time.seconds() + 100).to_string();
+            assert_eq!(attribute(events[0], "expiry"), Some(expiry.as_str()));
+
+            let response = close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(100, ATOM_DENOM)],
+            );
+            let events = pawn_events(&response);
+            assert_eq!(events.len(), 1);
+            assert_eq!(attribute(events[0], "action"), Some("closed"));
+            assert_eq!(attribute(events[0], "state"), Some("Closed"));
+            assert_eq!(attribute(events[0], "repaid"), Some("100"));
+        }
+
+        #[test]
+        fn test_claim_reports_default_then_close() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract);
+            accept(&mut app, &cw_template_contract);
+
+            app.update_block(|block| block.time = block.time.plus_seconds(101));
+            let response = close(&mut app, &cw_template_contract, BOB, vec![]);
+            let actions: Vec<_> = pawn_events(&response)
+                .into_iter()
+                .map(|event| attribute(event, "action").unwrap())
+                .collect();
+            assert_eq!(actions, vec!["defaulted", "closed"]);
+        }
+
+        #[test]
+        fn test_rejected_event() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract);
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::RejectProposition { proposition_id: 1 })
+                .unwrap();
+            let response = app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+            let events = pawn_events(&response);
+            assert_eq!(events.len(), 1);
+            assert_eq!(attribute(events[0], "action"), Some("rejected"));
+            assert_eq!(attribute(events[0], "state"), Some("Rejected"));
+        }
+    }
+
+    mod funds_mode_test {
+
+        use super::*;
+        use crate::asset::Asset;
+        use crate::msg::{ExecuteMsg, FundsMode, PropositionType};
+        use crate::ContractError;
+
+        fn set_funds_mode(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            funds_mode: FundsMode,
+        ) {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: Some(funds_mode),
+                    term_limits: None,
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+        }
+
+        fn create(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            funds: Vec<Coin>,
+        ) -> AnyResult<()> {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract.call_with_funds(msg, funds).unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).map(|_| ())
+        }
+
+        fn accept(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            funds: Vec<Coin>,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(ExecuteMsg::AcceptProposition { proposition_id: 1 }, funds)
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).map(|_| ())
+        }
+
+        #[test]
+        fn test_exact_rejects_overpayment() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let err = create(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(120, INJ_DENOM), Coin::new(5, USDT_DENOM)],
+            )
+            .unwrap_err();
+            match err.root_cause().downcast_ref::<ContractError>().unwrap() {
+                ContractError::PaymentError { expected, actual } => {
+                    assert_eq!(expected, &vec![Asset::native(110u128, INJ_DENOM)]);
+                    assert_eq!(
+                        actual,
+                        &vec![
+                            Asset::native(120u128, INJ_DENOM),
+                            Asset::native(5u128, USDT_DENOM)
+                        ]
+                    );
+                }
+                err => panic!("Unexpected error {err}"),
+            }
+
+            create(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(110, INJ_DENOM)],
+            )
+            .unwrap();
+
+            // A stray denom next to the exact amount is rejected too
+            assert!(accept(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(100, ATOM_DENOM), Coin::new(1, SOL_DENOM)]
+            )
+            .is_err());
+            accept(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(100, ATOM_DENOM)],
+            )
+            .unwrap();
+
+            assert_eq!(
+                balance(&app, cw_template_contract.addr().as_str(), USDT_DENOM),
+                Uint128::zero()
+            );
+        }
+
+        #[test]
+        fn test_refund_returns_overpayment() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            set_funds_mode(&mut app, &cw_template_contract, FundsMode::Refund);
+
+            create(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(120, INJ_DENOM), Coin::new(5, USDT_DENOM)],
+            )
+            .unwrap();
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(890));
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(1000));
+
+            accept(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(150, ATOM_DENOM), Coin::new(7, SOL_DENOM)],
+            )
+            .unwrap();
+            assert_eq!(balance(&app, BOB, ATOM_DENOM), Uint128::new(900));
+            assert_eq!(balance(&app, BOB, SOL_DENOM), Uint128::new(1000));
+
+            let contract = cw_template_contract.addr();
+            for denom in [ATOM_DENOM, USDT_DENOM, SOL_DENOM] {
+                assert_eq!(balance(&app, contract.as_str(), denom), Uint128::zero());
+            }
+            assert_eq!(
+                balance(&app, contract.as_str(), INJ_DENOM),
+                Uint128::new(100)
+            );
+        }
+
+        #[test]
+        fn test_refund_still_requires_enough() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            set_funds_mode(&mut app, &cw_template_contract, FundsMode::Refund);
+
+            assert!(create(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(100, INJ_DENOM), Coin::new(50, USDT_DENOM)]
+            )
+            .is_err());
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_funds_on_messages_taking_none() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(
+                &mut app,
+                &cw_template_contract,
+                vec![Coin::new(110, INJ_DENOM)],
+            )
+            .unwrap();
+
+            let reject = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::RejectProposition { proposition_id: 1 },
+                    vec![Coin::new(5, USDT_DENOM)],
+                )
+                .unwrap();
+            assert!(app.execute(Addr::unchecked(ALICE), reject.clone()).is_err());
+
+            set_funds_mode(&mut app, &cw_template_contract, FundsMode::Refund);
+            app.execute(Addr::unchecked(ALICE), reject).unwrap();
+            assert_eq!(balance(&app, ALICE, USDT_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(1000));
+        }
+    }
+
+    mod term_validation_test {
+
+        use super::*;
+        use crate::asset::AssetInfo;
+        use crate::msg::{ExecuteMsg, PropositionType};
+        use crate::state::{AmountLimit, TermLimits};
+        use crate::ContractError;
+
+        fn create_msg(
+            assets: u128,
+            period: u64,
+            expiry: u64,
+            contractor: Option<&str>,
+        ) -> ExecuteMsg {
+            ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(assets, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period,
+                expiry,
+                contractor: contractor.map(str::to_string),
+                designated_contractors: None,
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            }
+        }
+
+        fn create(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            msg: ExecuteMsg,
+        ) -> Option<ContractError> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg)
+                .err()
+                .map(|err| err.downcast().unwrap())
+        }
+
+        fn set_term_limits(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            term_limits: TermLimits,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: Some(term_limits),
+                    collateral_denoms: None,
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).map(|_| ())
+        }
+
+        #[test]
+        fn test_rejects_invalid_terms() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            let now = app.block_info().time.seconds();
+
+            let msg = create_msg(0, 100, now + 100, None);
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::InvalidAmount {})
+            ));
+
+            let msg = create_msg(100, 0, now + 100, None);
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::InvalidPeriod {})
+            ));
+
+            let msg = create_msg(100, 100, now - 1, None);
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::ExpiryInPast {})
+            ));
+
+            let msg = create_msg(100, 100, now + 100, Some(ALICE));
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::SelfContractor {})
+            ));
+
+            let msg = create_msg(100, 100, now + 100, Some(""));
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::InvalidAddress { .. })
+            ));
+
+            let msg = create_msg(100, 100, now + 100, Some(BOB));
+            assert!(create(&mut app, &cw_template_contract, msg).is_none());
+        }
+
+        #[test]
+        fn test_term_limits() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            let now = app.block_info().time.seconds();
+
+            // Inverted bounds are refused
+            assert!(set_term_limits(
+                &mut app,
+                &cw_template_contract,
+                TermLimits {
+                    min_period: 200,
+                    max_period: Some(100),
+                    amounts: vec![],
+                },
+            )
+            .is_err());
+            assert!(set_term_limits(
+                &mut app,
+                &cw_template_contract,
+                TermLimits {
+                    min_period: 0,
+                    max_period: None,
+                    amounts: vec![AmountLimit {
+                        info: AssetInfo::Native(ATOM_DENOM.to_string()),
+                        min: Uint128::new(100),
+                        max: Some(Uint128::new(10)),
+                    }],
+                },
+            )
+            .is_err());
+
+            set_term_limits(
+                &mut app,
+                &cw_template_contract,
+                TermLimits {
+                    min_period: 10,
+                    max_period: Some(50),
+                    amounts: vec![
+                        AmountLimit {
+                            info: AssetInfo::Native(ATOM_DENOM.to_string()),
+                            min: Uint128::new(10),
+                            max: Some(Uint128::new(100)),
+                        },
+                        // The INJ deposit is bounded on its own
+                        AmountLimit {
+                            info: AssetInfo::Native(INJ_DENOM.to_string()),
+                            min: Uint128::new(50),
+                            max: Some(Uint128::new(1000)),
+                        },
+                    ],
+                },
+            )
+            .unwrap();
+
+            let msg = create_msg(101, 50, now + 100, None);
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::InvalidAmount {})
+            ));
+
+            let msg = create_msg(100, 51, now + 100, None);
+            assert!(matches!(
+                create(&mut app, &cw_template_contract, msg),
+                Some(ContractError::InvalidPeriod {})
+            ));
+
+            let msg = create_msg(100, 50, now + 100, None);
+            assert!(create(&mut app, &cw_template_contract, msg).is_none());
+        }
+    }
+
+    mod private_offer_test {
+
+        use super::*;
+        use crate::msg::{ExecuteMsg, PropositionType, PropositionsResponse, QueryMsg};
+        use crate::ContractError;
+
+        fn create(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            contractor: Option<&str>,
+            designated_contractors: Option<Vec<&str>>,
+        ) {
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(100, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: contractor.map(str::to_string),
+                designated_contractors: designated_contractors
+                    .map(|addresses| addresses.into_iter().map(str::to_string).collect()),
+                installments: None,
+                ltv: None,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(110, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+        }
+
+        fn accept(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            proposition_id: u64,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        fn private_ids(
+            app: &App,
+            cw_template_contract: &CwTemplateContract,
+            contractor: &str,
+        ) -> Vec<u64> {
+            app.wrap()
+                .query_wasm_smart::<PropositionsResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPrivatePropositions {
+                        contractor: contractor.to_string(),
+                        start_before: None,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+                .propositions
+                .into_iter()
+                .map(|item| item.id)
+                .collect()
+        }
+
+        #[test]
+        fn test_only_designated_contractor_accepts() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract, Some(BOB), None);
+            create(&mut app, &cw_template_contract, None, None);
+
+            assert_eq!(private_ids(&app, &cw_template_contract, BOB), vec![1]);
+            assert!(private_ids(&app, &cw_template_contract, ADMIN).is_empty());
+
+            let err = accept(&mut app, &cw_template_contract, ADMIN, 1).unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::NotDesignatedContractor {}
+            ));
+
+            // Public propositions stay open to anyone
+            accept(&mut app, &cw_template_contract, ADMIN, 2).unwrap();
+
+            accept(&mut app, &cw_template_contract, BOB, 1).unwrap();
+            assert!(private_ids(&app, &cw_template_contract, BOB).is_empty());
+        }
+
+        #[test]
+        fn test_designated_contractor_list() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(
+                &mut app,
+                &cw_template_contract,
+                Some(BOB),
+                Some(vec![ADMIN, BOB]),
+            );
+
+            assert_eq!(private_ids(&app, &cw_template_contract, BOB), vec![1]);
+            assert_eq!(private_ids(&app, &cw_template_contract, ADMIN), vec![1]);
+
+            accept(&mut app, &cw_template_contract, ADMIN, 1).unwrap();
+            assert!(private_ids(&app, &cw_template_contract, BOB).is_empty());
+            assert!(private_ids(&app, &cw_template_contract, ADMIN).is_empty());
+        }
+
+        #[test]
+        fn test_rejected_offer_is_no_longer_listed() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            create(&mut app, &cw_template_contract, Some(BOB), None);
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::RejectProposition { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            assert!(private_ids(&app, &cw_template_contract, BOB).is_empty());
+        }
+    }
+
+    mod collateral_test {
+
+        use super::*;
+        use crate::asset::{Asset, AssetInfo};
+        use crate::msg::{ExecuteMsg, PropositionResponse, PropositionType, QueryMsg};
+        use crate::oracle::OracleType;
+        use crate::state::LoanToValue;
+        use crate::ContractError;
+
+        fn init_test_case(ltv: Option<LoanToValue>) -> (App, CwTemplateContract) {
+            accepted_loan(ltv, None)
+        }
+
+        fn accepted_loan(
+            ltv: Option<LoanToValue>,
+            installments: Option<u32>,
+        ) -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: None,
+                    collateral_denoms: Some(vec![AssetInfo::Native(SOL_DENOM.to_string())]),
+                    min_collateral_ratio: None,
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+            set_oracle_symbols(&mut app, &cw_template_contract, &[INJ_DENOM, ATOM_DENOM]);
+
+            app.init_modules(|router, _, storage| {
+                router
+                    .custom
+                    .set_price(storage, INJ_DENOM, ATOM_DENOM, Decimal::one())
+            });
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(200, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments,
+                ltv,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(210, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn add_collateral(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            funds: Vec<Coin>,
+        ) -> Option<ContractError> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(ExecuteMsg::AddCollateral { proposition_id: 1 }, funds)
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg)
+                .err()
+                .map(|err| err.downcast::<ContractError>().unwrap())
+        }
+
+        fn withdraw(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            amount: u128,
+            denom: &str,
+        ) -> Option<ContractError> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::WithdrawExcessCollateral {
+                    proposition_id: 1,
+                    amount: Uint128::new(amount),
+                    collateral: Some(AssetInfo::Native(denom.to_string())),
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg)
+                .err()
+                .map(|err| err.downcast::<ContractError>().unwrap())
+        }
+
+        fn set_min_collateral_ratio(app: &mut App, cw_template_contract: &CwTemplateContract) {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    treasury: None,
+                    fee_bps: None,
+                    fee_on_default: None,
+                    note_contract: None,
+                    grace_period: None,
+                    late_fee_bps: None,
+                    funds_mode: None,
+                    term_limits: None,
+                    collateral_denoms: None,
+                    min_collateral_ratio: Some(Decimal::percent(150)),
+                    token_contracts: None,
+                    oracle_symbols: None,
+                })
+                .unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+        }
+
+        fn close(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+            funds: Vec<Coin>,
+        ) {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(ExecuteMsg::CloseProposition { proposition_id: 1 }, funds)
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).unwrap();
+        }
+
+        #[test]
+        fn test_add_collateral() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            assert!(matches!(
+                add_collateral(
+                    &mut app,
+                    &cw_template_contract,
+                    BOB,
+                    vec![Coin::new(50, INJ_DENOM)]
+                ),
+                Some(ContractError::Unauthorized {})
+            ));
+            assert!(matches!(
+                add_collateral(
+                    &mut app,
+                    &cw_template_contract,
+                    ALICE,
+                    vec![Coin::new(50, USDT_DENOM)]
+                ),
+                Some(ContractError::InvalidCollateral {})
+            ));
+            assert!(matches!(
+                add_collateral(&mut app, &cw_template_contract, ALICE, vec![]),
+                Some(ContractError::InvalidAmount {})
+            ));
+
+            assert!(add_collateral(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(50, INJ_DENOM), Coin::new(20, SOL_DENOM)]
+            )
+            .is_none());
+            assert!(add_collateral(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(5, SOL_DENOM)]
+            )
+            .is_none());
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.deposit.amount, Uint128::new(250));
+            assert_eq!(
+                proposition.extra_collateral,
+                vec![Asset::native(25u128, SOL_DENOM)]
+            );
+
+            // Repaying returns every added coin
+            close(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(100, ATOM_DENOM)],
+            );
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_added_collateral_forfeited_on_default() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            assert!(add_collateral(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(50, INJ_DENOM), Coin::new(20, SOL_DENOM)]
+            )
+            .is_none());
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            close(&mut app, &cw_template_contract, BOB, vec![]);
+
+            assert_eq!(balance(&app, BOB, INJ_DENOM), Uint128::new(1260));
+            assert_eq!(balance(&app, BOB, SOL_DENOM), Uint128::new(1020));
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(980));
+        }
+
+        #[test]
+        fn test_withdraw_excess_collateral() {
+            let (mut app, cw_template_contract) = init_test_case(Some(LoanToValue {
+                max_ltv: Decimal::percent(60),
+                liquidation_ltv: Decimal::percent(80),
+                liquidation_bonus: Decimal::percent(5),
+                oracle_type: OracleType::PriceFeed,
+            }));
+
+            // Disabled until a ratio is configured
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 50, INJ_DENOM),
+                Some(ContractError::CollateralRatioBreached { .. })
+            ));
+
+            set_min_collateral_ratio(&mut app, &cw_template_contract);
+
+            // 140 INJ worth 140 ATOM against 100 ATOM
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 60, INJ_DENOM),
+                Some(ContractError::CollateralRatioBreached { .. })
+            ));
+            assert!(withdraw(&mut app, &cw_template_contract, 50, INJ_DENOM).is_none());
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(840));
+
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 1, INJ_DENOM),
+                Some(ContractError::CollateralRatioBreached { .. })
+            ));
+        }
+
+        #[test]
+        fn test_withdraw_without_oracle() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 50, INJ_DENOM),
+                Some(ContractError::InvalidLoanToValue {})
+            ));
+        }
+
+        #[test]
+        fn test_extra_collateral_counts_towards_ratio() {
+            let (mut app, cw_template_contract) = init_test_case(Some(LoanToValue {
+                max_ltv: Decimal::percent(60),
+                liquidation_ltv: Decimal::percent(80),
+                liquidation_bonus: Decimal::percent(5),
+                oracle_type: OracleType::PriceFeed,
+            }));
+            set_oracle_symbols(
+                &mut app,
+                &cw_template_contract,
+                &[INJ_DENOM, ATOM_DENOM, SOL_DENOM],
+            );
+            app.init_modules(|router, _, storage| {
+                router
+                    .custom
+                    .set_price(storage, SOL_DENOM, ATOM_DENOM, Decimal::one())
+            });
+            set_min_collateral_ratio(&mut app, &cw_template_contract);
+
+            assert!(add_collateral(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(100, SOL_DENOM)]
+            )
+            .is_none());
+
+            // 50 INJ and 100 SOL, worth 150 ATOM against 100 ATOM
+            assert!(withdraw(&mut app, &cw_template_contract, 150, INJ_DENOM).is_none());
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 1, SOL_DENOM),
+                Some(ContractError::CollateralRatioBreached { .. })
+            ));
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 1, USDT_DENOM),
+                Some(ContractError::InvalidCollateral {})
+            ));
+
+            assert!(add_collateral(
+                &mut app,
+                &cw_template_contract,
+                ALICE,
+                vec![Coin::new(100, INJ_DENOM)]
+            )
+            .is_none());
+            assert!(withdraw(&mut app, &cw_template_contract, 100, SOL_DENOM).is_none());
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(1000));
+
+            let proposition = app
+                .wrap()
+                .query_wasm_smart::<PropositionResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetProposition { proposition_id: 1 },
+                )
+                .unwrap()
+                .proposition;
+            assert_eq!(proposition.deposit.amount, Uint128::new(150));
+            assert!(proposition.extra_collateral.is_empty());
+        }
+
+        #[test]
+        fn test_withdraw_refused_after_missed_installment() {
+            let (mut app, cw_template_contract) = accepted_loan(
+                Some(LoanToValue {
+                    max_ltv: Decimal::percent(60),
+                    liquidation_ltv: Decimal::percent(80),
+                    liquidation_bonus: Decimal::percent(5),
+                    oracle_type: OracleType::PriceFeed,
+                }),
+                Some(4),
+            );
+            set_min_collateral_ratio(&mut app, &cw_template_contract);
+
+            app.update_block(|block| block.time = block.time.plus_seconds(26));
+            assert!(matches!(
+                withdraw(&mut app, &cw_template_contract, 10, INJ_DENOM),
+                Some(ContractError::InstallmentMissed {})
+            ));
+        }
+    }
+
+    mod substitution_test {
+
+        use super::*;
+        use crate::asset::{Asset, AssetInfo};
+        use crate::msg::{ExecuteMsg, PropositionType};
+        use crate::oracle::OracleType;
+        use crate::state::LoanToValue;
+        use crate::ContractError;
+
+        fn init_test_case(ltv: Option<LoanToValue>) -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            set_oracle_symbols(
+                &mut app,
+                &cw_template_contract,
+                &[INJ_DENOM, ATOM_DENOM, SOL_DENOM],
+            );
+
+            app.init_modules(|router, _, storage| {
+                router
+                    .custom
+                    .set_price(storage, INJ_DENOM, ATOM_DENOM, Decimal::one());
+                router
+                    .custom
+                    .set_price(storage, SOL_DENOM, ATOM_DENOM, Decimal::percent(50));
+            });
+
+            let msg = ExecuteMsg::CreateProposition {
+                proposition_type: PropositionType::Ask,
+                deposit: Coin::new(200, INJ_DENOM).into(),
+                assets: Coin::new(100, ATOM_DENOM).into(),
+                premium: Coin::new(10, INJ_DENOM).into(),
+                period: 100,
+                expiry: app.block_info().time.seconds() + 100,
+                contractor: None,
+                designated_contractors: None,
+                installments: None,
+                ltv,
+                min_fill: None,
+                interest_rate: None,
+            };
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(msg, vec![Coin::new(210, INJ_DENOM)])
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::AcceptProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(BOB), cosmos_msg).unwrap();
+
+            (app, cw_template_contract)
+        }
+
+        fn substitute(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            amount: u128,
+            denom: &str,
+        ) -> Option<ContractError> {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::SubstituteCollateral {
+                        proposition_id: 1,
+                        new_deposit: Coin::new(amount, denom).into(),
+                    },
+                    vec![Coin::new(amount, denom)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg)
+                .err()
+                .map(|err| err.downcast::<ContractError>().unwrap())
+        }
+
+        fn approve(
+            app: &mut App,
+            cw_template_contract: &CwTemplateContract,
+            sender: &str,
+        ) -> AnyResult<()> {
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::ApproveSubstitution { proposition_id: 1 })
+                .unwrap();
+            app.execute(Addr::unchecked(sender), cosmos_msg).map(|_| ())
+        }
+
+        fn repay(app: &mut App, cw_template_contract: &CwTemplateContract) {
+            let cosmos_msg = cw_template_contract
+                .call_with_funds(
+                    ExecuteMsg::CloseProposition { proposition_id: 1 },
+                    vec![Coin::new(100, ATOM_DENOM)],
+                )
+                .unwrap();
+            app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap();
+        }
+
+        #[test]
+        fn test_substitute_with_approval() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            assert!(substitute(&mut app, &cw_template_contract, 300, SOL_DENOM).is_none());
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(700));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(790));
+
+            assert!(approve(&mut app, &cw_template_contract, ALICE).is_err());
+            approve(&mut app, &cw_template_contract, BOB).unwrap();
+            assert!(approve(&mut app, &cw_template_contract, BOB).is_err());
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+
+            let loan = proposition(&app, &cw_template_contract);
+            let new_deposit = Asset::native(300u128, SOL_DENOM);
+            assert_eq!(loan.deposit, new_deposit);
+            assert_eq!(loan.pending_substitution, None);
+            assert_eq!(loan.substitutions.len(), 1);
+            assert_eq!(
+                loan.substitutions[0].previous,
+                Asset::native(200u128, INJ_DENOM)
+            );
+            assert_eq!(loan.substitutions[0].deposit, new_deposit);
+
+            // Repaying returns the substituted deposit
+            repay(&mut app, &cw_template_contract);
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(1000));
+        }
+
+        #[test]
+        fn test_substitute_valued_by_oracle() {
+            let (mut app, cw_template_contract) = init_test_case(Some(LoanToValue {
+                max_ltv: Decimal::percent(60),
+                liquidation_ltv: Decimal::percent(80),
+                liquidation_bonus: Decimal::percent(5),
+                oracle_type: OracleType::PriceFeed,
+            }));
+
+            // No oracle symbol configured for USDT
+            assert!(matches!(
+                substitute(&mut app, &cw_template_contract, 400, USDT_DENOM),
+                Some(ContractError::InvalidLoanToValue {})
+            ));
+
+            // 300 SOL worth 150 ATOM, less than the 200 INJ deposit
+            assert!(substitute(&mut app, &cw_template_contract, 300, SOL_DENOM).is_none());
+            assert!(proposition(&app, &cw_template_contract)
+                .pending_substitution
+                .is_some());
+
+            // Worth as much, replaces the pending one and goes through at once
+            assert!(substitute(&mut app, &cw_template_contract, 400, SOL_DENOM).is_none());
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(600));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+
+            let loan = proposition(&app, &cw_template_contract);
+            assert_eq!(loan.pending_substitution, None);
+            assert_eq!(loan.substitutions.len(), 1);
+            assert_eq!(loan.deposit, Asset::native(400u128, SOL_DENOM));
+        }
+
+        #[test]
+        fn test_pending_substitution_refunded_on_close() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            assert!(substitute(&mut app, &cw_template_contract, 300, SOL_DENOM).is_none());
+            repay(&mut app, &cw_template_contract);
+
+            assert_eq!(balance(&app, ALICE, SOL_DENOM), Uint128::new(1000));
+            assert_eq!(balance(&app, ALICE, INJ_DENOM), Uint128::new(990));
+            assert!(proposition(&app, &cw_template_contract)
+                .substitutions
+                .is_empty());
+        }
+
+        #[test]
+        fn test_substitute_refuses_nft() {
+            let (mut app, cw_template_contract) = init_test_case(None);
+
+            let cosmos_msg = cw_template_contract
+                .call(ExecuteMsg::SubstituteCollateral {
+                    proposition_id: 1,
+                    new_deposit: Asset {
+                        info: AssetInfo::Cw721 {
+                            contract: Addr::unchecked("nft"),
+                            token_id: "1".to_string(),
+                        },
+                        amount: Uint128::one(),
+                    },
+                })
+                .unwrap();
+            let err = app.execute(Addr::unchecked(ALICE), cosmos_msg).unwrap_err();
+            assert!(matches!(
+                err.downcast().unwrap(),
+                ContractError::InvalidNftCollateral {}
+            ));
+        }
+    }
 }
@@ -1,9 +1,14 @@
+pub mod asset;
 pub mod contract;
 mod error;
+pub mod events;
 pub mod helpers;
 pub mod integration_tests;
 pub mod macros;
+pub mod migrations;
 pub mod msg;
+pub mod note;
+pub mod oracle;
 pub mod state;
 
 pub use crate::error::ContractError;
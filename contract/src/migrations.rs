@@ -0,0 +1,106 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Empty, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::msg::{PropositionState, PropositionType};
+use crate::state::{propositions, Proposition, PRIVATE_OFFERS};
+
+/// `Proposition` as stored by 1.x, coin amounts only and no indexes
+#[cw_serde]
+pub struct PropositionV1 {
+    pub owner: Addr,
+    pub proposition_type: PropositionType,
+    pub state: PropositionState,
+    pub deposit: Coin,
+    pub assets: Coin,
+    pub premium: Coin,
+    pub period: u64,
+    pub expiry: u64,
+    pub contractor: Option<Addr>,
+}
+
+pub const PROPOSITIONS_V1: Map<u64, PropositionV1> = Map::new("propositions");
+
+/// Id of the last proposition rewritten by a 1.x migration that has more to
+/// go, the contract only takes `MigratePropositions` while it is set
+pub const MIGRATION_CURSOR: Item<u64> = Item::new("migration_cursor");
+
+pub const DEFAULT_MIGRATION_LIMIT: u32 = 100;
+
+pub fn migration_in_progress(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(MIGRATION_CURSOR.may_load(storage)?.is_some())
+}
+
+impl From<PropositionV1> for Proposition {
+    fn from(legacy: PropositionV1) -> Self {
+        // The contractor of an active 1.x proposition is the one it was meant for
+        let (contractor, designated_contractors) = match legacy.state {
+            PropositionState::Active => (None, legacy.contractor.into_iter().collect()),
+            _ => (legacy.contractor, vec![]),
+        };
+
+        Proposition {
+            owner: legacy.owner,
+            proposition_type: legacy.proposition_type,
+            state: legacy.state,
+            deposit: legacy.deposit.into(),
+            assets: legacy.assets.into(),
+            premium: legacy.premium.into(),
+            period: legacy.period,
+            expiry: legacy.expiry,
+            contractor,
+            installments: None,
+            schedule: vec![],
+            repaid: Uint128::zero(),
+            pending_extension: None,
+            ltv: None,
+            min_fill: None,
+            filled: Uint128::zero(),
+            vault: None,
+            notes: None,
+            interest_rate: None,
+            interest_accrued: Uint128::zero(),
+            accrued_at: 0,
+            grace_period: 0,
+            late_fee_bps: 0,
+            designated_contractors,
+            extra_collateral: vec![],
+            pending_substitution: None,
+            substitutions: vec![],
+        }
+    }
+}
+
+/// Rewrites up to `limit` 1.x propositions past the cursor in the current
+/// layout and builds their indexes, returns how many were migrated. The
+/// cursor is kept as long as some are left.
+pub fn migrate_propositions_v1(storage: &mut dyn Storage, limit: u32) -> StdResult<usize> {
+    let limit = limit.max(1) as usize;
+    let start = MIGRATION_CURSOR.may_load(storage)?.map(Bound::exclusive);
+
+    // Everything past the cursor is still in the 1.x layout, one item past
+    // the batch tells whether there is more
+    let mut legacy: Vec<(u64, PropositionV1)> = PROPOSITIONS_V1
+        .range(storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<_>>()?;
+    let done = legacy.len() <= limit;
+    legacy.truncate(limit);
+
+    // The stored values cannot be read as `Proposition`, so there are no
+    // old index entries to remove and `replace` is given none
+    for (id, proposition) in legacy.iter() {
+        let proposition: Proposition = proposition.clone().into();
+        propositions().replace(storage, *id, Some(&proposition), None)?;
+        for contractor in proposition.designated_contractors.iter() {
+            PRIVATE_OFFERS.save(storage, (contractor, *id), &Empty {})?;
+        }
+    }
+
+    match legacy.last() {
+        Some((id, _)) if !done => MIGRATION_CURSOR.save(storage, id)?,
+        _ => MIGRATION_CURSOR.remove(storage),
+    }
+
+    Ok(legacy.len())
+}
@@ -1,24 +1,54 @@
 use std::fmt;
 
-use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Decimal, Uint128, Uint64};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
+
+use crate::asset::{Asset, AssetInfo};
+use crate::state::{
+    Config, CounterOffer, Fill, LoanToValue, PauseFlags, Proposition, TermLimits, Vault,
+    VaultTerms,
+};
 
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// Defaults to the sender
+    pub owner: Option<String>,
+    pub treasury: String,
+    pub fee_bps: u16,
+    pub fee_on_default: bool,
+}
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Required when migrating from 1.x, which stored no config
+    pub owner: Option<String>,
+    pub treasury: Option<String>,
+    /// Propositions rewritten when migrating from 1.x, the rest is done with
+    /// `MigratePropositions`
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
     CreateProposition {
         proposition_type: PropositionType,
-        deposit: Coin,
-        assets: Coin,
-        premium: Coin,
+        deposit: Asset,
+        assets: Asset,
+        premium: Asset,
         period: u64,
         expiry: u64,
-        contractor: Option<Addr>,
+        /// Only this address may accept, `designated_contractors` adds more
+        contractor: Option<String>,
+        designated_contractors: Option<Vec<String>>,
+        installments: Option<u32>,
+        ltv: Option<LoanToValue>,
+        /// Bids only, lets several lenders fill `assets` in parts
+        min_fill: Option<Uint128>,
+        /// Annual interest rate paid with the repayment, `premium` must then be zero
+        interest_rate: Option<Decimal>,
     },
     RejectProposition {
         proposition_id: u64,
@@ -29,6 +59,173 @@ pub enum ExecuteMsg {
     CloseProposition {
         proposition_id: u64,
     },
+    /// Pay back part of the `assets` of an accepted loan, the deposit is
+    /// released once nothing is outstanding
+    RepayLoan {
+        proposition_id: u64,
+        amount: Uint128,
+    },
+    /// Ask for `extra_period` more on an accepted loan, `extra_premium` is
+    /// escrowed until the other side approves. Replaces any pending request.
+    RequestExtension {
+        proposition_id: u64,
+        extra_period: u64,
+        extra_premium: Asset,
+    },
+    ApproveExtension {
+        proposition_id: u64,
+    },
+    /// Anyone can close an accepted loan whose loan to value reached its
+    /// `liquidation_ltv`, earning the liquidation bonus
+    Liquidate {
+        proposition_id: u64,
+    },
+    /// Add the attached funds to the collateral of an accepted loan, in its
+    /// deposit denom or any of the config's `collateral_denoms`. Only the
+    /// party that posted the deposit may add to it.
+    AddCollateral {
+        proposition_id: u64,
+    },
+    /// Take `amount` of the `collateral` denom back from an oracle priced
+    /// loan, as long as it stays above the config's `min_collateral_ratio`
+    /// and no installment is missed. Unset `collateral` is the deposit denom.
+    WithdrawExcessCollateral {
+        proposition_id: u64,
+        amount: Uint128,
+        collateral: Option<AssetInfo>,
+    },
+    /// Swap the deposit of an accepted loan for `new_deposit`, escrowed with
+    /// the call. The swap is immediate when the oracle values it at least as
+    /// much as the current deposit. Loans with oracle limits only take a
+    /// fungible denom the config has an oracle symbol for. Otherwise the other
+    /// side has to approve it. Replaces any pending substitution.
+    SubstituteCollateral {
+        proposition_id: u64,
+        new_deposit: Asset,
+    },
+    ApproveSubstitution {
+        proposition_id: u64,
+    },
+    /// Owner only, unset fields are left unchanged
+    UpdateConfig {
+        owner: Option<String>,
+        treasury: Option<String>,
+        fee_bps: Option<u16>,
+        fee_on_default: Option<bool>,
+        note_contract: Option<String>,
+        grace_period: Option<u64>,
+        late_fee_bps: Option<u16>,
+        funds_mode: Option<FundsMode>,
+        term_limits: Option<TermLimits>,
+        collateral_denoms: Option<Vec<AssetInfo>>,
+        min_collateral_ratio: Option<Decimal>,
+        token_contracts: Option<Vec<String>>,
+        oracle_symbols: Option<Vec<(AssetInfo, String)>>,
+    },
+    /// Take `amount` of a syndicated Bid's `assets`, paying in the matching
+    /// share of deposit and premium. The loan starts once fully filled.
+    FillProposition {
+        proposition_id: u64,
+        amount: Uint128,
+    },
+    /// Bid owner only, starts a syndicated loan filled at least to `min_fill`
+    ActivateProposition {
+        proposition_id: u64,
+    },
+    /// Propose other terms for an active proposition, escrowing the side the
+    /// sender would pay in when accepting it
+    MakeCounterOffer {
+        proposition_id: u64,
+        deposit: Asset,
+        assets: Asset,
+        premium: Asset,
+        period: u64,
+    },
+    /// Proposition owner only, starts the loan on the offered terms and
+    /// refunds every other counter-offer
+    AcceptCounterOffer {
+        counter_offer_id: u64,
+    },
+    WithdrawCounterOffer {
+        counter_offer_id: u64,
+    },
+    /// Hand the sender's side of an accepted loan to `recipient`, who then
+    /// receives every later payout of that side
+    TransferPosition {
+        proposition_id: u64,
+        side: PositionSide,
+        recipient: String,
+    },
+    /// Mint the note of the sender's side of an accepted loan, the side then
+    /// follows the note. The side receiving repayment gets its note on acceptance.
+    MintNote {
+        proposition_id: u64,
+        side: PositionSide,
+    },
+    /// Open a lending pool for `assets`, the sender owns its terms
+    CreateVault {
+        assets: AssetInfo,
+        terms: VaultTerms,
+    },
+    /// Add `amount` of the vault's assets in exchange for shares. Shares held
+    /// before take their part of the collateral forfeited since, new shares
+    /// only share in later defaults.
+    DepositToVault {
+        vault_id: u64,
+        amount: Uint128,
+    },
+    /// Redeem `shares` for their part of the vault's liquidity. Pays out the
+    /// part of every held share in the collateral forfeited since the last
+    /// deposit or withdrawal.
+    WithdrawFromVault {
+        vault_id: u64,
+        shares: Uint128,
+    },
+    /// Draw `amount` from a vault on its terms, opening an accepted Ask
+    /// that is repaid and closed like any other loan
+    BorrowFromVault {
+        vault_id: u64,
+        amount: Uint128,
+    },
+    /// Owner only, halts or resumes one kind of operation
+    SetPaused {
+        operation: Operation,
+        paused: bool,
+    },
+    /// Rewrites the next `limit` 1.x propositions of an unfinished migration,
+    /// the only message taken until it is done
+    MigratePropositions {
+        limit: Option<u32>,
+    },
+    /// CW20 hook of one of the config's `token_contracts`, `msg` is any other
+    /// paying `ExecuteMsg` paid for with the received tokens. Further CW20
+    /// assets it requires are pulled from the allowance.
+    Receive(Cw20ReceiveMsg),
+    /// CW721 hook of one of the config's `token_contracts`, `msg` is a paying
+    /// `ExecuteMsg` taking the received NFT, such as a `CreateProposition` for
+    /// an Ask pawning it as its deposit
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+impl ExecuteMsg {
+    /// Messages that take a payment, the only ones a CW20 or CW721 hook may carry
+    pub fn is_payable(&self) -> bool {
+        matches!(
+            self,
+            ExecuteMsg::CreateProposition { .. }
+                | ExecuteMsg::AcceptProposition { .. }
+                | ExecuteMsg::FillProposition { .. }
+                | ExecuteMsg::CloseProposition { .. }
+                | ExecuteMsg::RepayLoan { .. }
+                | ExecuteMsg::RequestExtension { .. }
+                | ExecuteMsg::AddCollateral { .. }
+                | ExecuteMsg::SubstituteCollateral { .. }
+                | ExecuteMsg::MakeCounterOffer { .. }
+                | ExecuteMsg::AcceptCounterOffer { .. }
+                | ExecuteMsg::DepositToVault { .. }
+                | ExecuteMsg::BorrowFromVault { .. }
+        )
+    }
 }
 
 #[cw_serde]
@@ -44,32 +241,269 @@ impl fmt::Display for PropositionType {
     }
 }
 
+/// Roles of an accepted proposition, see `Proposition::get_lender` and
+/// `Proposition::get_borrower` for what each means per proposition type
+#[cw_serde]
+#[derive(Copy)]
+pub enum PositionSide {
+    Owner,
+    Contractor,
+}
+impl fmt::Display for PositionSide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[cw_serde]
 pub enum PropositionState {
     Active,
     Accepted,
     Closed,
     Rejected,
+    /// Active proposition nobody accepted before `expiry`, escrow refunded
+    Expired,
+    /// Accepted loan not repaid before `expiry`, deposit claimable
+    Defaulted,
+    /// Accepted loan closed early as its loan to value crossed the liquidation threshold
+    Liquidated,
+}
+impl fmt::Display for PropositionState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
 
 #[cw_serde]
 pub enum SudoMsg {
     BeginBlocker {},
+    SetPaused { operation: Operation, paused: bool },
+}
+
+/// Operations that can be paused, repaying and extending loans always stay open
+#[cw_serde]
+#[derive(Copy)]
+pub enum Operation {
+    Create,
+    Accept,
+    Reject,
+    Close,
+    Liquidate,
+}
+
+impl Operation {
+    pub fn of(msg: &ExecuteMsg) -> Option<Operation> {
+        match msg {
+            ExecuteMsg::CreateProposition { .. }
+            | ExecuteMsg::MakeCounterOffer { .. }
+            | ExecuteMsg::CreateVault { .. }
+            | ExecuteMsg::DepositToVault { .. } => Some(Operation::Create),
+            ExecuteMsg::AcceptProposition { .. }
+            | ExecuteMsg::AcceptCounterOffer { .. }
+            | ExecuteMsg::FillProposition { .. }
+            | ExecuteMsg::ActivateProposition { .. }
+            | ExecuteMsg::BorrowFromVault { .. } => Some(Operation::Accept),
+            ExecuteMsg::RejectProposition { .. } => Some(Operation::Reject),
+            ExecuteMsg::CloseProposition { .. } => Some(Operation::Close),
+            ExecuteMsg::Liquidate { .. } => Some(Operation::Liquidate),
+            _ => None,
+        }
+    }
 }
 
 #[cw_serde]
-// #[derive(QueryResponses)]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
-    // #[returns(Vec<Proposition>)]
+    #[returns(PropositionsResponse)]
     GetPropositions {
         start_before: Option<u64>,
         limit: Option<u32>,
     },
-    // #[returns(Proposition)]
+    #[returns(PropositionResponse)]
     GetProposition {
         proposition_id: u64,
     },
-    // #[returns(Uint64)]
+    #[returns(CountResponse)]
     GetPropositionCount {},
+    /// What repays an accepted loan now, errors for any other state
+    #[returns(Payoff)]
+    GetPayoff {
+        proposition_id: u64,
+    },
+    #[returns(LoanPhase)]
+    GetLoanPhase {
+        proposition_id: u64,
+    },
+    #[returns(Vec<Fill>)]
+    GetFills {
+        proposition_id: u64,
+    },
+    #[returns(Vec<(u64, CounterOffer)>)]
+    GetCounterOffers {
+        proposition_id: u64,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<(u64, CounterOffer)>)]
+    GetCounterOffersByOwner {
+        owner: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(Vault)]
+    GetVault {
+        vault_id: u64,
+    },
+    #[returns(Uint128)]
+    GetVaultShares {
+        vault_id: u64,
+        owner: String,
+    },
+    #[returns(Config)]
+    Config {},
+    #[returns(PauseFlags)]
+    PauseFlags {},
+    #[returns(PropositionsPage)]
+    SearchPropositions {
+        filter: Box<PropositionFilter>,
+        start_after: Option<u64>,
+        start_before: Option<u64>,
+        order: Option<SortOrder>,
+        limit: Option<u32>,
+    },
+    /// Indexed by the holders as of each proposition's last update, a note
+    /// changing hands in between is only picked up by `GetProposition`
+    #[returns(PropositionsResponse)]
+    GetPropositionsByOwner {
+        owner: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Indexed like `GetPropositionsByOwner`
+    #[returns(PropositionsResponse)]
+    GetPropositionsByContractor {
+        contractor: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Active private propositions `contractor` is designated on
+    #[returns(PropositionsResponse)]
+    GetPrivatePropositions {
+        contractor: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(PropositionsResponse)]
+    GetPropositionsByState {
+        state: PropositionState,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(PropositionsResponse)]
+    GetPropositionsByDepositDenom {
+        denom: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(PropositionsResponse)]
+    GetPropositionsByAssetsDenom {
+        denom: String,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// Criteria for `SearchPropositions`, every unset field matches anything.
+/// Denoms are native denoms or CW20 contract addresses, premium bounds
+/// compare amounts in the premium's own denom.
+#[cw_serde]
+#[derive(Default)]
+pub struct PropositionFilter {
+    pub proposition_type: Option<PropositionType>,
+    pub state: Option<PropositionState>,
+    pub owner: Option<String>,
+    pub contractor: Option<String>,
+    pub deposit_denom: Option<String>,
+    pub assets_denom: Option<String>,
+    pub min_premium: Option<Uint128>,
+    pub max_premium: Option<Uint128>,
+    pub min_period: Option<u64>,
+    pub max_period: Option<u64>,
+}
+
+#[cw_serde]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// How attached funds beyond what a call requires are handled
+#[cw_serde]
+#[derive(Default)]
+pub enum FundsMode {
+    /// Funds have to match the required assets exactly
+    #[default]
+    Exact,
+    /// The surplus is sent back to the sender in the same transaction
+    Refund,
+}
+
+/// Where an accepted loan stands relative to its expiry
+#[cw_serde]
+pub enum LoanPhase {
+    /// Repayable as agreed
+    Active,
+    /// Past expiry, still repayable with a late fee
+    Grace,
+    /// Past the grace period, the deposit is claimable
+    Defaulted,
+}
+
+/// What closing an accepted loan costs at the current block time
+#[cw_serde]
+pub struct Payoff {
+    pub principal: Asset,
+    pub interest: Asset,
+    /// Charged on the principal during the grace period
+    pub late_fee: Asset,
+}
+
+#[cw_serde]
+pub struct PropositionResponse {
+    pub id: u64,
+    pub proposition: Proposition,
+}
+
+impl From<(u64, Proposition)> for PropositionResponse {
+    fn from((id, proposition): (u64, Proposition)) -> Self {
+        PropositionResponse { id, proposition }
+    }
+}
+
+#[cw_serde]
+pub struct PropositionsResponse {
+    pub propositions: Vec<PropositionResponse>,
+}
+
+impl From<Vec<(u64, Proposition)>> for PropositionsResponse {
+    fn from(items: Vec<(u64, Proposition)>) -> Self {
+        PropositionsResponse {
+            propositions: items.into_iter().map(PropositionResponse::from).collect(),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct CountResponse {
+    pub count: Uint64,
+}
+
+#[cw_serde]
+pub struct PropositionsPage {
+    pub items: Vec<PropositionResponse>,
+    /// Id to pass as `start_after` (ascending) or `start_before` (descending)
+    /// to fetch the next page, `None` once the book is exhausted. A search
+    /// that hit its scan bound returns a short page along with a cursor.
+    pub next_cursor: Option<u64>,
 }
@@ -0,0 +1,102 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, WasmMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+
+use crate::asset::Asset;
+use crate::msg::PositionSide;
+use crate::oracle::InjectiveQueryWrapper;
+use crate::state::Proposition;
+
+/// On-chain metadata of a loan note, as of its mint
+#[cw_serde]
+pub struct NoteMetadata {
+    pub proposition_id: u64,
+    pub side: PositionSide,
+    pub principal: Asset,
+    pub deposit: Asset,
+    pub premium: Asset,
+    pub expiry: u64,
+}
+
+/// Mirrors the `mint` message of cw721-base, the note contract stores
+/// `NoteMetadata` as the token extension
+#[cw_serde]
+enum NoteExecuteMsg {
+    Mint(MintMsg),
+}
+
+#[cw_serde]
+struct MintMsg {
+    token_id: String,
+    owner: String,
+    token_uri: Option<String>,
+    extension: NoteMetadata,
+}
+
+pub fn note_token_id(proposition_id: u64, side: PositionSide) -> String {
+    format!("{proposition_id}-{}", side.to_string().to_lowercase())
+}
+
+/// Mints the note of `side` to its current holder
+pub fn mint_note_msg(
+    note_contract: &Addr,
+    proposition_id: u64,
+    proposition: &Proposition,
+    side: PositionSide,
+    holder: &Addr,
+) -> StdResult<CosmosMsg> {
+    let msg = NoteExecuteMsg::Mint(MintMsg {
+        token_id: note_token_id(proposition_id, side),
+        owner: holder.to_string(),
+        token_uri: None,
+        extension: NoteMetadata {
+            proposition_id,
+            side,
+            principal: proposition.assets.clone(),
+            deposit: proposition.deposit.clone(),
+            premium: proposition.premium.clone(),
+            expiry: proposition.expiry,
+        },
+    });
+
+    Ok(WasmMsg::Execute {
+        contract_addr: note_contract.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Replaces the holder of every side backed by a note with the note's current
+/// owner. A burned note leaves the side with the holder last stored.
+pub fn resolve_note_holders(
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    proposition_id: u64,
+    proposition: &mut Proposition,
+) -> StdResult<()> {
+    let notes = match &proposition.notes {
+        Some(notes) => notes.clone(),
+        None => return Ok(()),
+    };
+
+    for side in notes.sides {
+        let response: StdResult<OwnerOfResponse> = querier.query_wasm_smart(
+            &notes.contract,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: note_token_id(proposition_id, side),
+                include_expired: None,
+            },
+        );
+        let holder = match response {
+            Ok(response) => Addr::unchecked(response.owner),
+            Err(_) => continue,
+        };
+
+        match side {
+            PositionSide::Owner => proposition.owner = holder,
+            PositionSide::Contractor => proposition.contractor = Some(holder),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{CustomQuery, Decimal, QuerierWrapper, QueryRequest, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Custom query understood by the Injective chain, only the oracle module
+/// route is used here
+#[cw_serde]
+pub struct InjectiveQueryWrapper {
+    pub route: InjectiveRoute,
+    pub query_data: InjectiveQuery,
+}
+
+impl CustomQuery for InjectiveQueryWrapper {}
+
+#[cw_serde]
+pub enum InjectiveRoute {
+    Oracle,
+}
+
+#[cw_serde]
+pub enum InjectiveQuery {
+    OraclePrice {
+        oracle_type: i32,
+        base: String,
+        quote: String,
+    },
+}
+
+/// Mirrors the `OracleType` enum of the Injective oracle module
+#[cw_serde]
+#[derive(Copy)]
+pub enum OracleType {
+    Band,
+    PriceFeed,
+    Coinbase,
+    Chainlink,
+    Razor,
+    Dia,
+    Api3,
+    Uma,
+    Pyth,
+    BandIbc,
+    Provider,
+}
+
+impl OracleType {
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            OracleType::Band => 1,
+            OracleType::PriceFeed => 2,
+            OracleType::Coinbase => 3,
+            OracleType::Chainlink => 4,
+            OracleType::Razor => 5,
+            OracleType::Dia => 6,
+            OracleType::Api3 => 7,
+            OracleType::Uma => 8,
+            OracleType::Pyth => 9,
+            OracleType::BandIbc => 10,
+            OracleType::Provider => 11,
+        }
+    }
+}
+
+// Not `cw_serde`, the chain returns more fields than the pair price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OraclePriceResponse {
+    pub price_pair_state: Option<PricePairState>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricePairState {
+    pub pair_price: Decimal,
+}
+
+/// Price of one unit of `base` expressed in `quote`
+pub fn query_oracle_price(
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    oracle_type: OracleType,
+    base: &str,
+    quote: &str,
+) -> StdResult<Decimal> {
+    let request = QueryRequest::Custom(InjectiveQueryWrapper {
+        route: InjectiveRoute::Oracle,
+        query_data: InjectiveQuery::OraclePrice {
+            oracle_type: oracle_type.as_i32(),
+            base: base.to_string(),
+            quote: quote.to_string(),
+        },
+    });
+    let response: OraclePriceResponse = querier.query(&request)?;
+
+    response
+        .price_pair_state
+        .map(|state| state.pair_price)
+        .ok_or_else(|| StdError::generic_err(format!("No oracle price for {base}/{quote}")))
+}
@@ -1,23 +1,162 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Env};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{
+    Addr, Decimal, Empty, Env, QuerierWrapper, StdError, StdResult, Uint128, Uint64,
+};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
-use crate::msg::{PropositionState, PropositionType};
+use crate::asset::{merge_assets, Asset, AssetInfo};
+use crate::msg::{
+    FundsMode, LoanPhase, Operation, PositionSide, PropositionState, PropositionType,
+};
+use crate::oracle::{query_oracle_price, InjectiveQueryWrapper, OracleType};
 
 #[cw_serde]
 pub struct Proposition {
     pub owner: Addr,
     pub proposition_type: PropositionType,
     pub state: PropositionState,
-    pub deposit: Coin,
-    pub assets: Coin,
-    pub premium: Coin,
+    pub deposit: Asset,
+    pub assets: Asset,
+    pub premium: Asset,
     pub period: u64,
     pub expiry: u64,
     pub contractor: Option<Addr>,
+    /// Number of equal payments `assets` are repaid in, `None` for a single payment
+    #[serde(default)]
+    pub installments: Option<u32>,
+    /// Due dates fixed on acceptance, spread evenly over `period`
+    #[serde(default)]
+    pub schedule: Vec<Installment>,
+    /// Part of `assets` already paid back
+    #[serde(default)]
+    pub repaid: Uint128,
+    /// Extension asked for by the lender, waiting for the borrower's approval
+    #[serde(default)]
+    pub pending_extension: Option<Extension>,
+    /// Oracle priced limits, without them the deposit is only claimable on default
+    #[serde(default)]
+    pub ltv: Option<LoanToValue>,
+    /// Set on Bids open to several lenders, each filling part of `assets`.
+    /// The owner may start the loan once this much is filled.
+    #[serde(default)]
+    pub min_fill: Option<Uint128>,
+    /// Part of `assets` taken by lenders so far
+    #[serde(default)]
+    pub filled: Uint128,
+    /// Set on loans drawn from a vault, which then stands as the contractor
+    #[serde(default)]
+    pub vault: Option<u64>,
+    /// Sides held through a CW721 note rather than by address
+    #[serde(default)]
+    pub notes: Option<Notes>,
+    /// Annual rate charged per second on the outstanding `assets`, in place
+    /// of an upfront premium
+    #[serde(default)]
+    pub interest_rate: Option<Decimal>,
+    /// Interest owed as of `accrued_at`
+    #[serde(default)]
+    pub interest_accrued: Uint128,
+    #[serde(default)]
+    pub accrued_at: u64,
+    /// Time after `expiry` during which the loan can still be repaid, with a
+    /// late fee. Both are taken from the config on acceptance.
+    #[serde(default)]
+    pub grace_period: u64,
+    #[serde(default)]
+    pub late_fee_bps: u16,
+    /// Makes the proposition private, only these addresses may accept it
+    #[serde(default)]
+    pub designated_contractors: Vec<Addr>,
+    /// Collateral added to the loan in approved denoms other than the
+    /// deposit's, returned and forfeited along with the deposit. It counts
+    /// towards the loan to value when the config has an oracle symbol for it.
+    #[serde(default)]
+    pub extra_collateral: Vec<Asset>,
+    /// Deposit offered in place of the current one, waiting for the approval
+    /// of the party that provided `assets`
+    #[serde(default)]
+    pub pending_substitution: Option<Substitution>,
+    /// Every deposit swap so far, oldest first
+    #[serde(default)]
+    pub substitutions: Vec<SubstitutionRecord>,
+}
+
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Bounds the schedule walked on every repayment and default check
+pub const MAX_INSTALLMENTS: u32 = 120;
+
+/// Upper bound on the configured grace period, keeps the end of it in range
+pub const MAX_GRACE_PERIOD: u64 = SECONDS_PER_YEAR;
+
+#[cw_serde]
+pub struct Notes {
+    pub contract: Addr,
+    pub sides: Vec<PositionSide>,
+}
+
+/// A lender's share of a syndicated Bid, `deposit` and `premium` are paid in
+/// pro rata to `amount`
+#[cw_serde]
+pub struct Fill {
+    pub lender: Addr,
+    pub amount: Uint128,
+    pub deposit: Uint128,
+    pub premium: Uint128,
+}
+
+pub const FILLS: Map<(u64, &Addr), Fill> = Map::new("fills");
+
+/// Loan to value is the outstanding `assets` over the oracle value of the deposit
+#[cw_serde]
+pub struct LoanToValue {
+    /// Highest ratio a proposition can be accepted at
+    pub max_ltv: Decimal,
+    /// Ratio from which anyone can liquidate the loan
+    pub liquidation_ltv: Decimal,
+    /// Share of the deposit paid to the liquidator, the rest goes to the borrower
+    pub liquidation_bonus: Decimal,
+    /// Oracle the deposit and `assets` are priced by, under the symbols in
+    /// the config's `oracle_symbols`
+    pub oracle_type: OracleType,
+}
+
+impl LoanToValue {
+    pub fn is_valid(&self) -> bool {
+        !self.max_ltv.is_zero()
+            && self.max_ltv <= self.liquidation_ltv
+            && self.liquidation_bonus < Decimal::one()
+    }
+}
+
+#[cw_serde]
+pub struct Extension {
+    pub extra_period: u64,
+    /// Escrowed on request, paid to the borrower on approval
+    pub extra_premium: Asset,
+}
+
+#[cw_serde]
+pub struct Substitution {
+    /// Escrowed on request, becomes the deposit once swapped in
+    pub new_deposit: Asset,
+}
+
+#[cw_serde]
+pub struct SubstitutionRecord {
+    pub previous: Asset,
+    pub deposit: Asset,
+    pub time: u64,
+}
+
+#[cw_serde]
+pub struct Installment {
+    pub due: u64,
+    pub amount: Uint128,
 }
 
 impl Proposition {
+    /// Party that posted the deposit, receives `assets` and repays them
     pub fn get_lender(&self) -> Addr {
         match self.proposition_type {
             PropositionType::Ask {} => self.owner.clone(),
@@ -25,6 +164,7 @@ impl Proposition {
         }
     }
 
+    /// Party that provided `assets`, earns the premium and claims the deposit on default
     pub fn get_borrower(&self) -> Addr {
         match self.proposition_type {
             PropositionType::Ask {} => self.contractor.clone().unwrap(),
@@ -32,6 +172,38 @@ impl Proposition {
         }
     }
 
+    /// Side holding `get_lender`
+    pub fn lender_side(&self) -> PositionSide {
+        match self.proposition_type {
+            PropositionType::Ask => PositionSide::Owner,
+            PropositionType::Bid => PositionSide::Contractor,
+        }
+    }
+
+    /// Side holding `get_borrower`
+    pub fn borrower_side(&self) -> PositionSide {
+        match self.proposition_type {
+            PropositionType::Ask => PositionSide::Contractor,
+            PropositionType::Bid => PositionSide::Owner,
+        }
+    }
+
+    pub fn has_note(&self, side: PositionSide) -> bool {
+        self.notes
+            .as_ref()
+            .is_some_and(|notes| notes.sides.contains(&side))
+    }
+
+    /// Whether `address` may take the proposition, anyone for a public one
+    pub fn is_open_to(&self, address: &Addr) -> bool {
+        self.designated_contractors.is_empty() || self.designated_contractors.contains(address)
+    }
+
+    /// Whether the proposition is split between several lenders, see `FILLS`
+    pub fn is_syndicated(&self) -> bool {
+        self.min_fill.is_some()
+    }
+
     pub fn is_expired(&self, env: &Env) -> bool {
         env.block.time.seconds() > self.expiry
     }
@@ -39,7 +211,616 @@ impl Proposition {
     pub fn is_not_expired(&self, env: &Env) -> bool {
         !self.is_expired(env)
     }
+
+    /// Whether an accepted loan can still be repaid, grace period included
+    pub fn is_repayable(&self, env: &Env) -> bool {
+        env.block.time.seconds() <= self.repayable_until()
+    }
+
+    /// End of the grace period, never repayable past the last timestamp
+    pub fn repayable_until(&self) -> u64 {
+        self.expiry.saturating_add(self.grace_period)
+    }
+
+    pub fn phase(&self, env: &Env) -> LoanPhase {
+        if self.is_not_expired(env) {
+            LoanPhase::Active
+        } else if self.is_repayable(env) {
+            LoanPhase::Grace
+        } else {
+            LoanPhase::Defaulted
+        }
+    }
+
+    /// Late fee owed on repaying `amount` now, only charged in the grace period
+    pub fn late_fee(&self, env: &Env, amount: Uint128) -> Uint128 {
+        match self.phase(env) {
+            LoanPhase::Grace => amount.multiply_ratio(u128::from(self.late_fee_bps), MAX_FEE_BPS),
+            _ => Uint128::zero(),
+        }
+    }
+
+    pub fn outstanding(&self) -> Uint128 {
+        self.assets.amount.saturating_sub(self.repaid)
+    }
+
+    /// Whether `assets` can be split into `installments` non-zero payments
+    pub fn has_valid_installments(&self) -> bool {
+        self.installments.is_none_or(|count| {
+            count <= MAX_INSTALLMENTS && Uint128::from(count) <= self.assets.amount
+        })
+    }
+
+    pub fn schedule_installments(&mut self, start: u64) -> StdResult<()> {
+        let count = u64::from(self.installments.unwrap_or_default());
+        let total = self.assets.amount;
+
+        self.schedule = (1..=count)
+            .map(|i| {
+                let offset = Uint64::new(self.period).multiply_ratio(i, count);
+                Ok(Installment {
+                    due: Uint64::new(start).checked_add(offset)?.u64(),
+                    amount: total.multiply_ratio(i, count) - total.multiply_ratio(i - 1, count),
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(())
+    }
+
+    /// Pushes the expiry, and every installment not due yet, back by `extra_period`
+    pub fn extend(&mut self, now: u64, extra_period: u64) -> StdResult<()> {
+        let extra_period = Uint64::new(extra_period);
+        self.period = Uint64::new(self.period).checked_add(extra_period)?.u64();
+        self.expiry = Uint64::new(self.expiry).checked_add(extra_period)?.u64();
+
+        for installment in self.schedule.iter_mut().filter(|i| i.due >= now) {
+            installment.due = Uint64::new(installment.due)
+                .checked_add(extra_period)?
+                .u64();
+        }
+        Ok(())
+    }
+
+    /// An installment is missed once its due date passed and the
+    /// cumulative amount due by then is not repaid yet
+    pub fn has_missed_installment(&self, env: &Env) -> bool {
+        let now = env.block.time.seconds();
+        let due: Uint128 = self
+            .schedule
+            .iter()
+            .filter(|installment| now > installment.due)
+            .map(|installment| installment.amount)
+            .sum();
+
+        due > self.repaid
+    }
+
+    /// Current loan to value, `None` if the proposition has no oracle limits
+    pub fn current_ltv(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        config: &Config,
+    ) -> StdResult<Option<Decimal>> {
+        let collateral_value = match self.collateral_value(querier, config)? {
+            Some(collateral_value) => collateral_value,
+            None => return Ok(None),
+        };
+
+        // A worthless collateral cannot be divided by, it is as under-collateralised as it gets
+        Ok(Some(
+            Decimal::checked_from_ratio(self.outstanding(), collateral_value)
+                .unwrap_or(Decimal::MAX),
+        ))
+    }
+
+    /// Oracle value of the collateral over the outstanding `assets`, the
+    /// inverse of `current_ltv`
+    pub fn collateral_ratio(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        config: &Config,
+    ) -> StdResult<Option<Decimal>> {
+        let collateral_value = match self.collateral_value(querier, config)? {
+            Some(collateral_value) => collateral_value,
+            None => return Ok(None),
+        };
+
+        // Nothing left to secure
+        Ok(Some(
+            Decimal::checked_from_ratio(collateral_value, self.outstanding())
+                .unwrap_or(Decimal::MAX),
+        ))
+    }
+
+    /// Deposit and extra collateral valued in `assets`, `None` if the
+    /// proposition has no oracle limits. Extra collateral the config has no
+    /// oracle symbol for is worth nothing, so it cannot hold up liquidation.
+    fn collateral_value(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        config: &Config,
+    ) -> StdResult<Option<Uint128>> {
+        let ltv = match &self.ltv {
+            Some(ltv) => ltv,
+            None => return Ok(None),
+        };
+
+        let mut value = self.value_in_assets(querier, config, ltv.oracle_type, &self.deposit)?;
+        for asset in self.extra_collateral.iter() {
+            if config.oracle_symbol(&asset.info).is_some() {
+                value += self.value_in_assets(querier, config, ltv.oracle_type, asset)?;
+            }
+        }
+        Ok(Some(value))
+    }
+
+    fn value_in_assets(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        config: &Config,
+        oracle_type: OracleType,
+        asset: &Asset,
+    ) -> StdResult<Uint128> {
+        let price = config.oracle_price(querier, oracle_type, &asset.info, &self.assets.info)?;
+        Ok(asset.amount * price)
+    }
+
+    /// Whether the oracle values `substitution` at least as much as the current
+    /// deposit. Never the case without oracle limits, or for NFTs.
+    pub fn is_covered_by(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        config: &Config,
+        substitution: &Substitution,
+    ) -> StdResult<bool> {
+        let ltv = match &self.ltv {
+            Some(ltv) if !substitution.new_deposit.info.is_nft() => ltv,
+            _ => return Ok(false),
+        };
+
+        let new_value =
+            self.value_in_assets(querier, config, ltv.oracle_type, &substitution.new_deposit)?;
+        let deposit_value =
+            self.value_in_assets(querier, config, ltv.oracle_type, &self.deposit)?;
+        Ok(new_value >= deposit_value)
+    }
+
+    /// Deposit and extra collateral, everything returned on repayment
+    pub fn collateral(&self) -> Vec<Asset> {
+        let mut collateral = vec![self.deposit.clone()];
+        collateral.extend(self.extra_collateral.iter().cloned());
+        collateral
+    }
+
+    /// Interest owed at `now`, always zero on flat premium propositions
+    pub fn interest_due(&self, now: u64) -> Uint128 {
+        match self.interest_rate {
+            Some(rate) => {
+                let elapsed = now.saturating_sub(self.accrued_at);
+                self.interest_accrued
+                    + self.outstanding().multiply_ratio(elapsed, SECONDS_PER_YEAR) * rate
+            }
+            None => Uint128::zero(),
+        }
+    }
+
+    /// Moves the interest checkpoint to `now`, to be done before `repaid` changes
+    pub fn accrue_interest(&mut self, now: u64) {
+        self.interest_accrued = self.interest_due(now);
+        self.accrued_at = now;
+    }
+
+    /// Whether the party that provided `assets` may take the deposit
+    pub fn is_claimable(&self, env: &Env) -> bool {
+        !self.is_repayable(env) || self.has_missed_installment(env)
+    }
+
+    /// Assets the contractor pays in when taking the proposition
+    pub fn contractor_escrow(&self) -> Vec<Asset> {
+        match self.proposition_type {
+            PropositionType::Ask => vec![self.assets.clone()],
+            PropositionType::Bid => vec![self.deposit.clone(), self.premium.clone()],
+        }
+    }
+
+    /// Assets the owner escrowed when creating the proposition
+    pub fn owner_escrow(&self) -> Vec<Asset> {
+        match self.proposition_type {
+            PropositionType::Ask => vec![self.deposit.clone(), self.premium.clone()],
+            PropositionType::Bid => vec![self.assets.clone()],
+        }
+    }
+}
+
+pub struct PropositionIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, Proposition, u64>,
+    // Propositions without a contractor are indexed under an empty string
+    pub contractor: MultiIndex<'a, String, Proposition, u64>,
+    pub state: MultiIndex<'a, String, Proposition, u64>,
+    // Native denom or CW20 / CW721 contract address
+    pub deposit_denom: MultiIndex<'a, String, Proposition, u64>,
+    pub assets_denom: MultiIndex<'a, String, Proposition, u64>,
+    // (state, expiry) so each state can be walked in expiry order, accepted
+    // loans are keyed at the end of their grace period
+    pub expiry: MultiIndex<'a, (String, u64), Proposition, u64>,
+}
+
+impl<'a> IndexList<Proposition> for PropositionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Proposition>> + '_> {
+        let v: Vec<&dyn Index<Proposition>> = vec![
+            &self.owner,
+            &self.contractor,
+            &self.state,
+            &self.deposit_denom,
+            &self.assets_denom,
+            &self.expiry,
+        ];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn propositions<'a>() -> IndexedMap<'a, u64, Proposition, PropositionIndexes<'a>> {
+    let indexes = PropositionIndexes {
+        owner: MultiIndex::new(
+            |_pk, p| p.owner.clone(),
+            "propositions",
+            "propositions__owner",
+        ),
+        contractor: MultiIndex::new(
+            |_pk, p| {
+                p.contractor
+                    .as_ref()
+                    .map(Addr::to_string)
+                    .unwrap_or_default()
+            },
+            "propositions",
+            "propositions__contractor",
+        ),
+        state: MultiIndex::new(
+            |_pk, p| p.state.to_string(),
+            "propositions",
+            "propositions__state",
+        ),
+        deposit_denom: MultiIndex::new(
+            |_pk, p| p.deposit.info.denom(),
+            "propositions",
+            "propositions__deposit_denom",
+        ),
+        assets_denom: MultiIndex::new(
+            |_pk, p| p.assets.info.denom(),
+            "propositions",
+            "propositions__assets_denom",
+        ),
+        expiry: MultiIndex::new(
+            |_pk, p| (p.state.to_string(), p.repayable_until()),
+            "propositions",
+            "propositions__expiry",
+        ),
+    };
+    IndexedMap::new("propositions", indexes)
 }
 
-pub const PROPOSITIONS: Map<u64, Proposition> = Map::new("propositions");
 pub const PROPOSITION_COUNT: Item<u64> = Item::new("proposition_count");
+
+/// Alternative terms for an active proposition, the counterparty's side is
+/// escrowed until the offer is accepted or withdrawn
+#[cw_serde]
+pub struct CounterOffer {
+    pub proposition_id: u64,
+    pub owner: Addr,
+    pub deposit: Asset,
+    pub assets: Asset,
+    pub premium: Asset,
+    pub period: u64,
+}
+
+impl CounterOffer {
+    /// The proposition as it reads once this offer is accepted
+    pub fn apply_to(&self, proposition: &Proposition) -> Proposition {
+        Proposition {
+            deposit: self.deposit.clone(),
+            assets: self.assets.clone(),
+            premium: self.premium.clone(),
+            period: self.period,
+            contractor: Some(self.owner.clone()),
+            ..proposition.clone()
+        }
+    }
+}
+
+pub struct CounterOfferIndexes<'a> {
+    pub proposition: MultiIndex<'a, u64, CounterOffer, u64>,
+    pub owner: MultiIndex<'a, Addr, CounterOffer, u64>,
+}
+
+impl<'a> IndexList<CounterOffer> for CounterOfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CounterOffer>> + '_> {
+        let v: Vec<&dyn Index<CounterOffer>> = vec![&self.proposition, &self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn counter_offers<'a>() -> IndexedMap<'a, u64, CounterOffer, CounterOfferIndexes<'a>> {
+    let indexes = CounterOfferIndexes {
+        proposition: MultiIndex::new(
+            |_pk, o| o.proposition_id,
+            "counter_offers",
+            "counter_offers__proposition",
+        ),
+        owner: MultiIndex::new(
+            |_pk, o| o.owner.clone(),
+            "counter_offers",
+            "counter_offers__owner",
+        ),
+    };
+    IndexedMap::new("counter_offers", indexes)
+}
+
+pub const COUNTER_OFFER_COUNT: Item<u64> = Item::new("counter_offer_count");
+
+/// Active private propositions by designated contractor
+pub const PRIVATE_OFFERS: Map<(&Addr, u64), Empty> = Map::new("private_offers");
+
+#[cw_serde]
+pub struct Config {
+    pub owner: Addr,
+    /// Receives the protocol fees
+    pub treasury: Addr,
+    /// Fee in basis points taken from the premium on acceptance
+    pub fee_bps: u16,
+    /// Whether the fee is also taken from deposits claimed on default
+    pub fee_on_default: bool,
+    /// CW721 contract loan notes are minted on, this contract must be its minter
+    #[serde(default)]
+    pub note_contract: Option<Addr>,
+    /// Seconds an accepted loan stays repayable after its expiry
+    #[serde(default)]
+    pub grace_period: u64,
+    /// Fee in basis points of the repayment, paid to the party that provided
+    /// `assets` when repaying in the grace period
+    #[serde(default)]
+    pub late_fee_bps: u16,
+    /// Whether surplus funds attached to a call are rejected or refunded
+    #[serde(default)]
+    pub funds_mode: FundsMode,
+    #[serde(default)]
+    pub term_limits: TermLimits,
+    /// Denoms borrowers may add as collateral besides their loan's deposit denom
+    #[serde(default)]
+    pub collateral_denoms: Vec<AssetInfo>,
+    /// Value of the deposit and priced extra collateral over outstanding
+    /// `assets` a loan must keep after a collateral withdrawal, zero disables
+    /// withdrawals
+    #[serde(default)]
+    pub min_collateral_ratio: Decimal,
+    /// CW20 and CW721 contracts whose hooks are trusted to report the sender
+    #[serde(default)]
+    pub token_contracts: Vec<Addr>,
+    /// Symbols assets are priced by on the oracle in loan to value checks
+    #[serde(default)]
+    pub oracle_symbols: Vec<(AssetInfo, String)>,
+}
+
+impl Config {
+    /// Protocol fee on `asset`, NFTs cannot be split and pay none
+    pub fn fee(&self, asset: &Asset) -> Asset {
+        if asset.info.is_nft() {
+            return asset.with_amount(Uint128::zero());
+        }
+        asset.with_amount(
+            asset
+                .amount
+                .multiply_ratio(u128::from(self.fee_bps), MAX_FEE_BPS),
+        )
+    }
+
+    pub fn oracle_symbol(&self, info: &AssetInfo) -> Option<&str> {
+        self.oracle_symbols
+            .iter()
+            .find(|(asset, _)| asset == info)
+            .map(|(_, symbol)| symbol.as_str())
+    }
+
+    /// Price of one unit of `base` in `quote`, both need an oracle symbol
+    pub fn oracle_price(
+        &self,
+        querier: &QuerierWrapper<InjectiveQueryWrapper>,
+        oracle_type: OracleType,
+        base: &AssetInfo,
+        quote: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let symbol = |info: &AssetInfo| {
+            self.oracle_symbol(info)
+                .ok_or_else(|| StdError::generic_err(format!("No oracle symbol for {info}")))
+        };
+        query_oracle_price(querier, oracle_type, symbol(base)?, symbol(quote)?)
+    }
+}
+
+pub const MAX_FEE_BPS: u128 = 10_000;
+
+/// Bounds on the terms of new propositions, counter-offers and vault loans,
+/// unset upper bounds are unlimited. Amounts are bounded per denom, denoms
+/// without an entry and NFTs are not bounded.
+#[cw_serde]
+#[derive(Default)]
+pub struct TermLimits {
+    pub min_period: u64,
+    pub max_period: Option<u64>,
+    pub amounts: Vec<AmountLimit>,
+}
+
+/// Bounds on the raw amount of a single fungible denom
+#[cw_serde]
+pub struct AmountLimit {
+    pub info: AssetInfo,
+    pub min: Uint128,
+    pub max: Option<Uint128>,
+}
+
+impl AmountLimit {
+    pub fn allows(&self, amount: Uint128) -> bool {
+        amount >= self.min && self.max.is_none_or(|max| amount <= max)
+    }
+}
+
+impl TermLimits {
+    pub fn allows_period(&self, period: u64) -> bool {
+        period >= self.min_period && self.max_period.is_none_or(|max| period <= max)
+    }
+
+    pub fn allows_amount(&self, asset: &Asset) -> bool {
+        self.amounts
+            .iter()
+            .find(|limit| limit.info == asset.info)
+            .is_none_or(|limit| limit.allows(asset.amount))
+    }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Pool of `assets` lent on the vault's own terms, lenders hold shares of
+/// `liquidity` plus what is `lent`
+#[cw_serde]
+pub struct Vault {
+    /// Sets the terms
+    pub owner: Addr,
+    pub assets: AssetInfo,
+    pub terms: VaultTerms,
+    pub total_shares: Uint128,
+    /// Held by the contract, available to borrow or withdraw
+    pub liquidity: Uint128,
+    /// Drawn by borrowers and not repaid yet
+    pub lent: Uint128,
+    /// Forfeited by defaulted loans and not claimed by the share holders yet
+    #[serde(default)]
+    pub collateral: Vec<Asset>,
+    /// Forfeited collateral accrued per share, holders claim what it grew by
+    /// since their checkpoint
+    #[serde(default)]
+    pub collateral_per_share: Vec<(AssetInfo, Decimal)>,
+}
+
+impl Vault {
+    pub fn total_assets(&self) -> Uint128 {
+        self.liquidity + self.lent
+    }
+
+    /// Shares minted for `amount`, one per unit in an empty vault
+    pub fn shares_for(&self, amount: Uint128) -> Uint128 {
+        if self.total_shares.is_zero() {
+            return amount;
+        }
+        amount.multiply_ratio(self.total_shares, self.total_assets())
+    }
+
+    /// Assets redeemed for `shares` at the current share price
+    pub fn assets_for(&self, shares: Uint128) -> Uint128 {
+        shares.multiply_ratio(self.total_assets(), self.total_shares)
+    }
+
+    /// Splits `forfeited` over the shares held at this point, `false` if
+    /// there are none to take it
+    pub fn accrue_collateral(&mut self, forfeited: Vec<Asset>) -> StdResult<bool> {
+        if self.total_shares.is_zero() {
+            return Ok(false);
+        }
+        for asset in merge_assets(forfeited) {
+            let per_share = Decimal::checked_from_ratio(asset.amount, self.total_shares)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            match self
+                .collateral_per_share
+                .iter_mut()
+                .find(|(info, _)| *info == asset.info)
+            {
+                Some((_, accrued)) => *accrued = accrued.checked_add(per_share)?,
+                None => self
+                    .collateral_per_share
+                    .push((asset.info.clone(), per_share)),
+            }
+            self.collateral.push(asset);
+        }
+        self.collateral = merge_assets(std::mem::take(&mut self.collateral));
+        Ok(true)
+    }
+
+    /// Collateral forfeited since `checkpoint` that goes with `shares`
+    pub fn collateral_owed(
+        &self,
+        shares: Uint128,
+        checkpoint: &[(AssetInfo, Decimal)],
+    ) -> Vec<Asset> {
+        let owed = self
+            .collateral_per_share
+            .iter()
+            .map(|(info, accrued)| {
+                let seen = checkpoint
+                    .iter()
+                    .find(|(seen_info, _)| seen_info == info)
+                    .map(|(_, seen)| *seen)
+                    .unwrap_or_default();
+                Asset {
+                    info: info.clone(),
+                    amount: shares * (*accrued - seen),
+                }
+            })
+            .collect();
+        merge_assets(owed)
+    }
+}
+
+#[cw_serde]
+pub struct VaultTerms {
+    /// Collateral accepted by the vault
+    pub deposit: AssetInfo,
+    /// Deposit required per unit borrowed
+    pub collateral_ratio: Decimal,
+    /// Premium per unit borrowed, paid upfront in the vault's assets
+    pub premium_rate: Decimal,
+    pub period: u64,
+}
+
+pub const VAULTS: Map<u64, Vault> = Map::new("vaults");
+
+pub const VAULT_COUNT: Item<u64> = Item::new("vault_count");
+
+pub const VAULT_SHARES: Map<(u64, &Addr), Uint128> = Map::new("vault_shares");
+
+/// `Vault::collateral_per_share` as of the holder's last deposit or withdrawal
+pub const VAULT_CHECKPOINTS: Map<(u64, &Addr), Vec<(AssetInfo, Decimal)>> =
+    Map::new("vault_checkpoints");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseFlags {
+    pub create: bool,
+    pub accept: bool,
+    pub reject: bool,
+    pub close: bool,
+    pub liquidate: bool,
+}
+
+impl PauseFlags {
+    pub fn is_paused(&self, operation: Operation) -> bool {
+        match operation {
+            Operation::Create => self.create,
+            Operation::Accept => self.accept,
+            Operation::Reject => self.reject,
+            Operation::Close => self.close,
+            Operation::Liquidate => self.liquidate,
+        }
+    }
+
+    pub fn set(&mut self, operation: Operation, paused: bool) {
+        let flag = match operation {
+            Operation::Create => &mut self.create,
+            Operation::Accept => &mut self.accept,
+            Operation::Reject => &mut self.reject,
+            Operation::Close => &mut self.close,
+            Operation::Liquidate => &mut self.liquidate,
+        };
+        *flag = paused;
+    }
+}
+
+pub const PAUSE_FLAGS: Item<PauseFlags> = Item::new("pause_flags");
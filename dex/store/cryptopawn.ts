@@ -3,11 +3,17 @@ import { Coin } from '@injectivelabs/ts-types'
 import { BigNumber } from '@injectivelabs/utils'
 import { msgBroadcastClient, wasmApi } from '~~/app/Services'
 import { WalletStoreState } from '~~/store/wallet'
-import { Proposition, PropositionState, PropositionType } from '~~/types/cryptopawn'
+import { Asset, Proposition, PropositionState, PropositionType } from '~~/types/cryptopawn'
 
 const contractAddress = 'inj1ntkgeswdwx6jhg0q7n9rtunqxsvnsnf5k5vvm8'
 const EMPTY_FUNDS = [{ amount: '0', denom: 'inj' }]
 
+type ChainProposition = Omit<Proposition, 'deposit' | 'assets' | 'premium'> & {
+  deposit: Asset
+  assets: Asset
+  premium: Asset
+}
+
 export const useCryptoPawnStore = defineStore('cryptopawn', {
   state: () => ({ offers: [] }),
   getters: {
@@ -30,7 +36,15 @@ export const useCryptoPawnStore = defineStore('cryptopawn', {
 
 
       this.$patch({
-        offers: [...data.map((x: any) => ({ id: x[0], ...x[1] }))]
+        offers: [
+          ...data.propositions.map((x: any) => ({
+            id: x.id,
+            ...x.proposition,
+            deposit: fromChainAsset(x.proposition.deposit),
+            assets: fromChainAsset(x.proposition.assets),
+            premium: fromChainAsset(x.proposition.premium)
+          }))
+        ]
       })
     },
 
@@ -115,18 +129,18 @@ const offerService = (offer: Proposition) => {
     fundsToChain() {
       const { assets, deposit, premium } = offer
       return {
-        assets: {
+        assets: toChainAsset({
           amount: transformSpotQuantityToChainQuantityToFixed(assets),
           denom: assets.denom
-        },
-        deposit: {
+        }),
+        deposit: toChainAsset({
           amount: transformSpotQuantityToChainQuantityToFixed(deposit),
           denom: deposit.denom
-        },
-        premium: {
+        }),
+        premium: toChainAsset({
           amount: transformSpotQuantityToChainQuantityToFixed(premium),
           denom: premium.denom
-        }
+        })
       }
     },
     assetsFundsToChain() {
@@ -206,7 +220,7 @@ const chainService = (wallet: WalletStoreState) => {
         message
       })
     },
-    async createProposition(offer: Proposition, funds: Array<Coin>) {
+    async createProposition(offer: ChainProposition, funds: Array<Coin>) {
       const message = MsgExecuteContractCompat.fromJSON({
         contractAddress,
         sender: wallet.injectiveAddress,
@@ -247,6 +261,25 @@ const broadcastMessage = ({ message, senderAddress }: { message: MsgExecuteContr
   })
 }
 
+// CW20 and CW721 assets are shown under their contract address
+const fromChainAsset = ({ info, amount }: Asset) => {
+  if ('native' in info) {
+    return { amount, denom: info.native }
+  }
+  if ('cw20' in info) {
+    return { amount, denom: info.cw20 }
+  }
+
+  return { amount, denom: info.cw721.contract }
+}
+
+const toChainAsset = ({ amount, denom }: { amount: string; denom: string }): Asset => {
+  return {
+    info: { native: denom },
+    amount
+  }
+}
+
 const transformFunds = (funds: Record<string, BigNumber>) => {
   return Object.entries(funds).map(([denom, amount]) => {
     return {
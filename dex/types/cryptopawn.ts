@@ -7,7 +7,10 @@ export enum PropositionState {
   Active = 'active',
   Accepted = 'accepted',
   Closed = 'closed',
-  Rejected = 'rejected'
+  Rejected = 'rejected',
+  Expired = 'expired',
+  Defaulted = 'defaulted',
+  Liquidated = 'liquidated'
 }
 
 type Coin = {
@@ -15,6 +18,17 @@ type Coin = {
   amount: string
 }
 
+export type AssetInfo =
+  | { native: string }
+  | { cw20: string }
+  | { cw721: { contract: string; token_id: string } }
+
+// Wire format of the contract's deposit, assets and premium
+export type Asset = {
+  info: AssetInfo
+  amount: string
+}
+
 export type Proposition = {
   id?: string
   owner?: string
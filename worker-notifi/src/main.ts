@@ -58,22 +58,34 @@ ws.onopen = function () {
 ws.onmessage = async function  (event) {
   const msg = JSON.parse(event.data.toString())
   if (msg.result && msg.result.data && msg.result.data.value) {
-    const obj = Object.fromEntries(
-      msg.result.data.value.TxResult.result.events
-        .find((x: any) => x.type === 'wasm')
-        .attributes.map((x: any) => [
+    // A single tx can emit several transitions, a claim is defaulted then closed
+    const pawnEvents = msg.result.data.value.TxResult.result.events.filter(
+      (x: any) => x.type === 'wasm-crypto_pawn'
+    )
+
+    for (const pawnEvent of pawnEvents) {
+      const obj = Object.fromEntries(
+        pawnEvent.attributes.map((x: any) => [
           Buffer.from(x.key, 'base64').toString('utf-8'),
           Buffer.from(x.value, 'base64').toString('utf-8')
         ])
-    )
-    console.log(obj);
-    if (obj.method == 'accept_proposition') {
-      await sendMessage(obj.owner, 'Your proposition has been accepted')
-    }
+      )
+      console.log(obj);
+      if (obj.version !== '1') {
+        continue
+      }
+
+      if (obj.action == 'accepted') {
+        await sendMessage(obj.owner, 'Your proposition has been accepted')
+      }
 
-    if (obj.method == 'close_proposition') {
-      await sendMessage(obj.owner, 'Your proposition has been closed')
-      await sendMessage(obj.contractor, 'Your proposition has been closed')
+      if (obj.action == 'closed') {
+        await sendMessage(obj.owner, 'Your proposition has been closed')
+        // Syndicated loans have no single contractor to notify
+        if (obj.contractor) {
+          await sendMessage(obj.contractor, 'Your proposition has been closed')
+        }
+      }
     }
   }
 }